@@ -0,0 +1,124 @@
+use soroban_sdk::{ contractevent, contracttype, Address, Env, IntoVal, Symbol, Val };
+
+// Why an admin or role check failed; each contract maps these onto its own error codes
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AccessError {
+    Unauthorized,
+    NoPendingAdmin,
+}
+
+// Instance keys for the admin. They encode like the `AdminAddress` and `PendingAdmin`
+// variants of each contract's DataKey, which held these entries before.
+#[contracttype(export = false)]
+#[derive(Clone)]
+enum AccessKey {
+    AdminAddress,
+    PendingAdmin, // successor proposed by the admin
+}
+
+#[contractevent(topics = ["admin_proposed"], data_format = "single-value")]
+pub struct AdminProposedEvent {
+    pub new_admin: Address,
+}
+
+#[contractevent(topics = ["admin_changed"])]
+pub struct AdminChangedEvent {
+    pub previous_admin: Address,
+    pub new_admin: Address,
+}
+
+#[contractevent(topics = ["admin_renounced"], data_format = "single-value")]
+pub struct AdminRenouncedEvent {
+    pub previous_admin: Address,
+}
+
+// Internal: (role, account) -> true while granted; encodes like `DataKey::RoleMember(role, account)`
+fn role_key(env: &Env, role: Val, account: &Address) -> (Symbol, Val, Address) {
+    (Symbol::new(env, "RoleMember"), role, account.clone())
+}
+
+// Current admin; None before initialization and after the admin renounced
+pub fn admin(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&AccessKey::AdminAddress)
+}
+
+// Installs the first admin; callers check that the contract is not yet initialized
+pub fn set_admin(env: &Env, admin: &Address) {
+    env.storage().instance().set(&AccessKey::AdminAddress, admin);
+}
+
+pub fn pending_admin(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&AccessKey::PendingAdmin)
+}
+
+// Requires the admin's authorization and returns the admin
+pub fn require_admin(env: &Env) -> Result<Address, AccessError> {
+    let admin = admin(env).ok_or(AccessError::Unauthorized)?;
+    admin.require_auth();
+    Ok(admin)
+}
+
+pub fn has_role<R: IntoVal<Env, Val>>(env: &Env, role: R, account: &Address) -> bool {
+    admin(env).as_ref() == Some(account) ||
+        env.storage().instance().has(&role_key(env, role.into_val(env), account))
+}
+
+// Requires `caller`'s authorization and that it is the admin or holds `role`
+pub fn require_role<R: IntoVal<Env, Val>>(env: &Env, role: R, caller: &Address) -> Result<(), AccessError> {
+    caller.require_auth();
+
+    if !has_role(env, role, caller) {
+        return Err(AccessError::Unauthorized);
+    }
+    Ok(())
+}
+
+// The admin nominates a successor; nothing changes until the successor accepts.
+// Proposing again replaces the nominee.
+pub fn propose_admin(env: &Env, new_admin: &Address) -> Result<(), AccessError> {
+    require_admin(env)?;
+
+    env.storage().instance().set(&AccessKey::PendingAdmin, new_admin);
+    AdminProposedEvent { new_admin: new_admin.clone() }.publish(env);
+    Ok(())
+}
+
+pub fn accept_admin(env: &Env) -> Result<(), AccessError> {
+    let new_admin = pending_admin(env).ok_or(AccessError::NoPendingAdmin)?;
+    new_admin.require_auth();
+
+    let previous_admin = admin(env).ok_or(AccessError::Unauthorized)?;
+    env.storage().instance().set(&AccessKey::AdminAddress, &new_admin);
+    env.storage().instance().remove(&AccessKey::PendingAdmin);
+
+    AdminChangedEvent { previous_admin, new_admin }.publish(env);
+    Ok(())
+}
+
+// Removes the admin for good, along with any pending nominee. Roles already
+// granted keep working.
+pub fn renounce_admin(env: &Env) -> Result<(), AccessError> {
+    let previous_admin = require_admin(env)?;
+
+    env.storage().instance().remove(&AccessKey::AdminAddress);
+    env.storage().instance().remove(&AccessKey::PendingAdmin);
+
+    AdminRenouncedEvent { previous_admin }.publish(env);
+    Ok(())
+}
+
+// Role grants and revocations are announced by the contract, whose events carry its own
+// Role type
+pub fn grant_role<R: IntoVal<Env, Val>>(env: &Env, role: R, account: &Address) -> Result<(), AccessError> {
+    require_admin(env)?;
+
+    env.storage().instance().set(&role_key(env, role.into_val(env), account), &true);
+    Ok(())
+}
+
+pub fn revoke_role<R: IntoVal<Env, Val>>(env: &Env, role: R, account: &Address) -> Result<(), AccessError> {
+    require_admin(env)?;
+
+    env.storage().instance().remove(&role_key(env, role.into_val(env), account));
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use soroban_sdk::{ Env, IntoVal, Map, Symbol, TryFromVal, Val };
+
+// Ledgers in a day at five seconds per ledger
+const DAY_IN_LEDGERS: u32 = 17_280;
+
+// The contract instance lives about a month past its last call
+const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+// Per-entity entries live about three months past their last read or write
+pub const PERSISTENT_BUMP_AMOUNT: u32 = 90 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - 30 * DAY_IN_LEDGERS;
+
+// Keep the contract instance, and the config it holds, from being archived
+pub fn extend_instance(env: &Env) {
+    env.storage().instance().extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+// Read a persistent entry, extending its TTL if it exists
+pub fn read<K, V>(env: &Env, key: &K) -> Option<V>
+    where K: IntoVal<Env, Val>, V: TryFromVal<Env, Val>
+{
+    let value = env.storage().persistent().get(key);
+    if value.is_some() {
+        extend(env, key);
+    }
+    value
+}
+
+// Write a persistent entry and extend its TTL
+pub fn write<K, V>(env: &Env, key: &K, value: &V)
+    where K: IntoVal<Env, Val>, V: IntoVal<Env, Val>
+{
+    env.storage().persistent().set(key, value);
+    extend(env, key);
+}
+
+pub fn has<K: IntoVal<Env, Val>>(env: &Env, key: &K) -> bool {
+    env.storage().persistent().has(key)
+}
+
+pub fn remove<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+    env.storage().persistent().remove(key);
+}
+
+// Extend a persistent entry's TTL, returning false if there is no such entry
+pub fn extend<K: IntoVal<Env, Val>>(env: &Env, key: &K) -> bool {
+    if !has(env, key) {
+        return false;
+    }
+    env.storage().persistent().extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    true
+}
+
+// Whether a stored struct has `field`. Tells layouts apart before decoding, since decoding a
+// struct with missing fields traps rather than returning an error.
+pub fn has_field(env: &Env, value: &Val, field: &str) -> bool {
+    Map::<Symbol, Val>::try_from_val(env, value)
+        .map(|fields| fields.contains_key(Symbol::new(env, field)))
+        .unwrap_or(false)
+}
+
+// Move an entry that an older layout kept in instance storage over to persistent storage.
+// Returns false if there was nothing to move.
+pub fn migrate<K: IntoVal<Env, Val>>(env: &Env, key: &K) -> bool {
+    let value: Option<Val> = env.storage().instance().get(key);
+    match value {
+        Some(value) => {
+            env.storage().instance().remove(key);
+            write(env, key, &value);
+            true
+        }
+        None => false,
+    }
+}
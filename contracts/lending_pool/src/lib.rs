@@ -1,17 +1,95 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, token};
+use soroban_sdk::{
+    contract, contracterror, contractevent, contractimpl, contracttype, token, Address, BytesN, Env, TryFromVal,
+    Val, Vec,
+};
+
+use common::access::{ self, AccessError };
+use common::storage;
+
+// Storage layout version written by `initialize` and brought up to date by `migrate`
+const SCHEMA_VERSION: u32 = 1;
+
+// Lender positions are tracked in two parts. Shares carry principal: the share price
+// (total_assets / total_shares) only moves when losses are written off or recovered.
+// Interest stays out of the share price; repayments raise AccumulatedInterestPerShare and
+// each lender's reward_debt checkpoint decides what `claim_interest` pays. A position is
+// worth convert_to_assets(shares) + pending_interest(lender).
+
+// Virtual balance added to both sides of the share price, so that rounding can never
+// hand a newer depositor's funds to a handful of shares left behind after losses
+const VIRTUAL_SHARES: i128 = 1;
+const VIRTUAL_ASSETS: i128 = 1;
+
+// Fixed-point scale for AccumulatedInterestPerShare
+const INTEREST_PRECISION: i128 = 1_000_000_000_000;
+
+// Scale the original pool used for its interest accumulator, per deposited unit
+const LEGACY_INTEREST_PRECISION: i128 = 1_000_000_000;
+
+// Highest APR the rate model may reach at 100% utilization, in basis points
+const MAX_BORROW_RATE: u64 = 100_000;
+
+// Bounds for the admin-set utilization cap, in basis points
+const MIN_UTILIZATION_CAP: u32 = 5000;
+const MAX_UTILIZATION_CAP: u32 = 9500;
 
 #[contracttype]
 #[derive(Clone)]
 pub struct LenderInfo {
+    pub deposit_amount: i128,
+    pub deposit_timestamp: u64,
+    pub earned_interest: i128,  // interest claimed so far
+    pub shares: i128,
+    pub reward_debt: i128,  // shares × AccumulatedInterestPerShare at the last checkpoint
+    pub unclaimed_interest: i128,  // interest banked at checkpoints, not yet claimed
+    pub share_percentage: u32,  // in basis points (10000 = 100%), computed on read
+}
+
+// Lender position as the original pool stored it, before schema version 1
+#[contracttype(export = false)]
+#[derive(Clone)]
+struct LegacyLenderInfo {
     pub deposit_amount: i128,
     pub deposit_timestamp: u64,
     pub earned_interest: i128,
-    pub share_percentage: u32,  // in basis points (10000 = 100%)
+    pub share_percentage: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateModel {
+    pub base_rate: u32,  // APR at 0% utilization, in basis points
+    pub slope_low: u32,  // APR added between 0% and optimal utilization
+    pub slope_high: u32,  // APR added between optimal and 100% utilization
+    pub optimal_utilization: u32,  // kink, in basis points (8000 = 80%)
+}
+
+// Pool operations that can be paused on their own. Withdrawals of available
+// liquidity, interest claims and repayments are never paused.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PauseScope {
+    /// New deposits
+    Deposits,
+    /// New loans drawn by the LoanManager
+    Borrows,
+}
+
+// Duties the admin can hand out without giving away the admin key.
+// The admin itself may act in every role.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    /// Pauses and resumes deposits and borrowing
+    Pauser,
+    /// Tunes the rate model and utilization cap
+    RiskManager,
 }
 
 #[contracttype]
+#[derive(Clone)]
 pub enum DataKey {
     TotalLiquidity,
     TotalBorrowed,
@@ -19,10 +97,146 @@ pub enum DataKey {
     LenderInfo(Address),
     LoanManagerAddress,
     USDCTokenAddress,
-    BaseInterestRate,
+    RateModel,
     MaxUtilization,
+    Paused(PauseScope), // present while the scope is paused
+    TotalShares,
     AccumulatedInterestPerShare,
-    AdminAddress,
+    TotalInterestClaimed,
+    TotalLosses,
+    TotalRecovered,
+    SchemaVersion,
+    BaseInterestRate, // flat APR kept before schema version 1, replaced by RateModel
+    LegacyInterestPerShare, // the original pool's accumulator, per deposited unit
+    LegacyInterestHeld, // interest the original pool held that unconverted lenders can still be owed
+    HeldInterest, // interest repaid while no shares were outstanding, owed to the next depositor
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    /// The pool has already been initialized
+    AlreadyInitialized = 1,
+    /// The pool has not been initialized yet
+    NotInitialized = 2,
+    /// The amount must be positive (or non-negative for repayments), and principal
+    /// returned or written off can't exceed what is lent out
+    InvalidAmount = 3,
+    /// The lender has never deposited into the pool
+    LenderNotFound = 4,
+    /// The lender's deposit is smaller than the requested withdrawal
+    InsufficientBalance = 5,
+    /// The pool does not have enough unborrowed liquidity
+    InsufficientLiquidity = 6,
+    /// The rate model parameters are out of bounds
+    InvalidRateModel = 7,
+    /// The borrow would push utilization above the pool's cap
+    UtilizationCapExceeded = 8,
+    /// The utilization cap is outside the allowed bounds
+    InvalidUtilizationCap = 9,
+    /// The caller is not the admin and does not hold the required role
+    Unauthorized = 10,
+    /// No admin transfer has been proposed
+    NoPendingAdmin = 11,
+    /// The operation is paused
+    Paused = 12,
+    /// Losses wiped out the pool's assets while shares are still outstanding
+    PoolInsolvent = 13,
+}
+
+impl From<AccessError> for Error {
+    fn from(error: AccessError) -> Self {
+        match error {
+            AccessError::Unauthorized => Error::Unauthorized,
+            AccessError::NoPendingAdmin => Error::NoPendingAdmin,
+        }
+    }
+}
+
+#[contractevent(topics = ["role_granted"], data_format = "single-value")]
+pub struct RoleGrantedEvent {
+    #[topic]
+    pub role: Role,
+    pub account: Address,
+}
+
+#[contractevent(topics = ["role_revoked"], data_format = "single-value")]
+pub struct RoleRevokedEvent {
+    #[topic]
+    pub role: Role,
+    pub account: Address,
+}
+
+#[contractevent(topics = ["deposit"], data_format = "single-value")]
+pub struct DepositEvent {
+    #[topic]
+    pub lender: Address,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["withdraw"], data_format = "single-value")]
+pub struct WithdrawEvent {
+    #[topic]
+    pub lender: Address,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["claim_interest"], data_format = "single-value")]
+pub struct ClaimInterestEvent {
+    #[topic]
+    pub lender: Address,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["borrow"], data_format = "single-value")]
+pub struct BorrowEvent {
+    #[topic]
+    pub loan_id: u64,
+    #[topic]
+    pub borrower: Address,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["repay"], data_format = "single-value")]
+pub struct RepayEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["write_off"], data_format = "single-value")]
+pub struct WriteOffEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["recover"], data_format = "single-value")]
+pub struct RecoverEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["paused"], data_format = "single-value")]
+pub struct PausedEvent {
+    pub scope: PauseScope,
+}
+
+#[contractevent(topics = ["unpaused"], data_format = "single-value")]
+pub struct UnpausedEvent {
+    pub scope: PauseScope,
+}
+
+#[contractevent(topics = ["upgraded"], data_format = "single-value")]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+}
+
+#[contractevent(topics = ["migrated"], data_format = "single-value")]
+pub struct MigratedEvent {
+    pub schema_version: u32,
 }
 
 #[contract]
@@ -36,170 +250,306 @@ impl LendingPool {
         loan_manager: Address,
         usdc_token: Address,
         base_rate: u32,
-    ) {
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
         admin.require_auth();
 
-        let existing: Option<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::LoanManagerAddress);
-        assert!(existing.is_none(), "Pool already initialized");
+        if env.storage().instance().has(&DataKey::LoanManagerAddress) {
+            return Err(Error::AlreadyInitialized);
+        }
 
         env.storage().instance().set(&DataKey::LoanManagerAddress, &loan_manager);
         env.storage().instance().set(&DataKey::USDCTokenAddress, &usdc_token);
-        env.storage().instance().set(&DataKey::BaseInterestRate, &base_rate);
+        env.storage().instance().set(&DataKey::RateModel, &Self::default_rate_model(base_rate));
         env.storage().instance().set(&DataKey::MaxUtilization, &9000u32);
         env.storage().instance().set(&DataKey::TotalLiquidity, &0i128);
         env.storage().instance().set(&DataKey::TotalBorrowed, &0i128);
         env.storage().instance().set(&DataKey::TotalInterestEarned, &0i128);
-        env.storage().instance().set(&DataKey::AdminAddress, &admin);
+        env.storage().instance().set(&DataKey::TotalShares, &0i128);
+        access::set_admin(&env, &admin);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+
+        Ok(())
+    }
+
+    // Admin replaces the contract's code. Data is left as is; call `migrate` afterwards
+    // if the new code expects a newer storage layout.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        UpgradedEvent { wasm_hash: new_wasm_hash }.publish(&env);
+
+        Ok(())
+    }
+
+    // Storage layout version the contract's data is in; 0 for deployments that predate versioning
+    pub fn schema_version(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::SchemaVersion).unwrap_or(0)
+    }
+
+    // Admin brings stored data up to SCHEMA_VERSION and returns the version it is in afterwards
+    pub fn migrate(env: Env) -> Result<u32, Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        let mut version = Self::schema_version(env.clone());
+
+        // 0 -> 1: lender positions were already in persistent storage; the original pool's
+        // settings and totals move to the share-based layout
+        if version == 0 {
+            Self::migrate_to_v1(&env);
+            version = 1;
+            env.storage().instance().set(&DataKey::SchemaVersion, &version);
+            MigratedEvent { schema_version: version }.publish(&env);
+        }
+
+        Ok(version)
+    }
+
+    pub fn get_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::admin(&env)
+    }
+
+    // Successor named by `propose_admin` who has not accepted yet
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::pending_admin(&env)
+    }
+
+    // Admin nominates a successor, who takes over by calling `accept_admin`
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::propose_admin(&env, &new_admin)?)
     }
 
-    pub fn deposit(env: Env, lender: Address, amount: i128) {
+    // The nominated successor takes over as admin
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::accept_admin(&env)?)
+    }
+
+    // Admin gives up control for good; admin-only calls fail from then on
+    pub fn renounce_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::renounce_admin(&env)?)
+    }
+
+    // Admin lets `account` act in `role`
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::grant_role(&env, role, &account)?;
+        RoleGrantedEvent { role, account }.publish(&env);
+        Ok(())
+    }
+
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::revoke_role(&env, role, &account)?;
+        RoleRevokedEvent { role, account }.publish(&env);
+        Ok(())
+    }
+
+    // Whether `account` may act in `role`; the admin may act in every role
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        storage::extend_instance(&env);
+
+        access::has_role(&env, role, &account)
+    }
+
+    // Pauser stops `scope` until it is unpaused; withdrawals and repayments keep working
+    pub fn pause(env: Env, caller: Address, scope: PauseScope) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::Pauser, &caller)?;
+
+        env.storage().instance().set(&DataKey::Paused(scope), &true);
+        PausedEvent { scope }.publish(&env);
+        Ok(())
+    }
+
+    pub fn unpause(env: Env, caller: Address, scope: PauseScope) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::Pauser, &caller)?;
+
+        env.storage().instance().remove(&DataKey::Paused(scope));
+        UnpausedEvent { scope }.publish(&env);
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env, scope: PauseScope) -> bool {
+        storage::extend_instance(&env);
+
+        env.storage().instance().has(&DataKey::Paused(scope))
+    }
+
+    pub fn deposit(env: Env, lender: Address, amount: i128) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
         lender.require_auth();
+
+        Self::require_not_paused(&env, PauseScope::Deposits)?;
         
-        assert!(amount > 0, "Amount must be positive");
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Shares left worthless by write-offs would take a cut of every new deposit
+        if Self::total_assets(env.clone()) == 0 && Self::total_shares(env.clone()) > 0 {
+            return Err(Error::PoolInsolvent);
+        }
+
+        // Mint shares at the current share price
+        let shares = Self::preview_deposit(env.clone(), amount);
+        if shares <= 0 {
+            return Err(Error::InvalidAmount);
+        }
         
         // Transfer USDC from lender to contract
         let usdc_token_address: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
         let usdc_token = token::Client::new(&env, &usdc_token_address);
         
-        usdc_token.transfer(&lender, &env.current_contract_address(), &amount);
+        usdc_token.transfer(&lender, env.current_contract_address(), &amount);
         
         // Update lender info
-        let mut lender_info = env.storage().persistent()
-            .get(&DataKey::LenderInfo(lender.clone()))
+        let mut lender_info = Self::read_lender(&env, &lender)
             .unwrap_or_else(|| LenderInfo {
                 deposit_amount: 0,
                 deposit_timestamp: env.ledger().timestamp(),
                 earned_interest: 0,
+                shares: 0,
+                reward_debt: 0,
+                unclaimed_interest: 0,
                 share_percentage: 0,
             });
-            
-        // Update totals
-        let mut total_liquidity: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::TotalLiquidity)
-            .unwrap_or(0);
-        total_liquidity += amount;
-        env.storage()
-            .instance()
-            .set(&DataKey::TotalLiquidity, &total_liquidity);
+
+        // Checkpoint interest before the share balance changes
+        let acc_interest = Self::accumulated_interest_per_share(&env);
+        Self::accrue_interest(&mut lender_info, acc_interest);
 
         lender_info.deposit_amount += amount;
-        lender_info.share_percentage = if total_liquidity > 0 {
-            ((lender_info.deposit_amount as u128 * 10000) / total_liquidity as u128) as u32
-        } else {
-            10000
-        };
+        lender_info.shares += shares;
+        Self::reset_reward_debt(&mut lender_info, acc_interest);
+
+        storage::write(&env, &DataKey::LenderInfo(lender.clone()), &lender_info);
+
+        // Update totals
+        let total_liquidity = Self::total_assets(env.clone());
+        let total_shares = Self::total_shares(env.clone());
+        env.storage().instance().set(&DataKey::TotalLiquidity, &(total_liquidity + amount));
+        env.storage().instance().set(&DataKey::TotalShares, &(total_shares + shares));
+
+        // Interest repaid while the pool was empty goes to the lenders who refill it
+        let held_interest: i128 = env.storage().instance().get(&DataKey::HeldInterest).unwrap_or(0);
+        if held_interest > 0 {
+            env.storage().instance().remove(&DataKey::HeldInterest);
+            Self::distribute_interest(&env, held_interest);
+        }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::LenderInfo(lender.clone()), &lender_info);
+        DepositEvent { lender, amount }.publish(&env);
 
-        env.events().publish(("deposit", lender.clone()), amount);
+        Ok(())
     }
     
     // Lender withdraws USDC
-    pub fn withdraw(env: Env, lender: Address, amount: i128) {
+    pub fn withdraw(env: Env, lender: Address, amount: i128) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
         lender.require_auth();
         
-        assert!(amount > 0, "Amount must be positive");
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
         
-        let mut lender_info: LenderInfo = env.storage().persistent()
-            .get(&DataKey::LenderInfo(lender.clone()))
-            .expect("Lender not found");
-            
-        assert!(lender_info.deposit_amount >= amount, "Insufficient balance");
+        let mut lender_info: LenderInfo = Self::read_lender(&env, &lender)
+            .ok_or(Error::LenderNotFound)?;
+
+        // Burn enough shares to cover the amount, rounding against the lender
+        let shares = Self::preview_withdraw(env.clone(), amount);
+        if shares > lender_info.shares {
+            return Err(Error::InsufficientBalance);
+        }
         
         // Get available liquidity
-        let total_liquidity: i128 = env.storage().instance().get(&DataKey::TotalLiquidity).unwrap_or(0);
-        let total_borrowed: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap_or(0);
-        let available_liquidity = total_liquidity - total_borrowed;
+        let total_liquidity = Self::total_assets(env.clone());
+        let available_liquidity = Self::get_available_liquidity(env.clone());
         
-        assert!(amount <= available_liquidity, "Insufficient liquidity in the pool");
+        if amount > available_liquidity {
+            return Err(Error::InsufficientLiquidity);
+        }
         
         // Transfer USDC back to lender
         let usdc_token_address: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
         let usdc_token = token::Client::new(&env, &usdc_token_address);
+        usdc_token.transfer(&env.current_contract_address(), &lender, &amount);
         
-        // Calculate and transfer earned interest first
-        let current_interest_per_share: i128 = env.storage().instance()
-            .get(&DataKey::AccumulatedInterestPerShare)
-            .unwrap_or(0);
-            
-        // Calculate pending interest based on the lender's current deposit
-        let pending_interest = (lender_info.deposit_amount as i128 * current_interest_per_share) / 1_000_000_000
-            - lender_info.earned_interest;
-        
-        // Transfer principal and interest
-        if pending_interest > 0 {
-            // Transfer principal + interest
-            usdc_token.transfer(
-                &env.current_contract_address(), 
-                &lender, 
-                &(amount + pending_interest)
-            );
-            
-            // Update total interest earned
-            let total_interest_earned: i128 = env.storage().instance()
-                .get(&DataKey::TotalInterestEarned)
-                .unwrap_or(0);
-            
-            if total_interest_earned >= pending_interest {
-                env.storage().instance().set(
-                    &DataKey::TotalInterestEarned,
-                    &(total_interest_earned - pending_interest)
-                );
-            }
-            
-            lender_info.earned_interest += pending_interest;
-        } else {
-            // Only transfer principal if no interest is due
-            usdc_token.transfer(&env.current_contract_address(), &lender, &amount);
-        }
-        
-        // Update lender info
-        lender_info.deposit_amount -= amount;
-        
-        // Update total liquidity
-        let new_total_liquidity = total_liquidity - amount;
-        env.storage().instance().set(&DataKey::TotalLiquidity, &new_total_liquidity);
-        
-        // Update share percentage if there's still liquidity
-        if new_total_liquidity > 0 {
-            lender_info.share_percentage = ((lender_info.deposit_amount as u128 * 10000) / new_total_liquidity as u128) as u32;
+        // Checkpoint interest before the share balance changes
+        let acc_interest = Self::accumulated_interest_per_share(&env);
+        Self::accrue_interest(&mut lender_info, acc_interest);
+
+        // Release the matching part of the lender's deposit
+        let principal = if shares == lender_info.shares {
+            lender_info.deposit_amount
         } else {
-            lender_info.share_percentage = 0;
-        }
+            (lender_info.deposit_amount * shares) / lender_info.shares
+        };
+        lender_info.deposit_amount -= principal;
+        lender_info.shares -= shares;
+        Self::reset_reward_debt(&mut lender_info, acc_interest);
         
         // Save updated lender info
-        env.storage().persistent().set(&DataKey::LenderInfo(lender.clone()), &lender_info);
+        storage::write(&env, &DataKey::LenderInfo(lender.clone()), &lender_info);
+
+        // Update totals
+        let total_shares = Self::total_shares(env.clone());
+        env.storage().instance().set(&DataKey::TotalLiquidity, &(total_liquidity - amount));
+        env.storage().instance().set(&DataKey::TotalShares, &(total_shares - shares));
         
         // Emit withdraw event
-        env.events().publish(("withdraw", lender.clone()), amount);
+        WithdrawEvent { lender, amount }.publish(&env);
+
+        Ok(())
     }
     
     // Borrow from pool (called by LoanManager only)
-    pub fn borrow(env: Env, amount: i128, borrower: Address, loan_id: u64) {
-        // Only loan manager should call this. Because loan manager calls this as part of an admin-
-        // initiated flow, we rely on the stored address and do not expose this in the UI.
-        // (Soroban 23 contracts cannot easily assert cross-contract auth without recording auth data.)
-        let _loan_manager: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::LoanManagerAddress)
-            .expect("Loan manager not configured");
+    pub fn borrow(env: Env, amount: i128, borrower: Address, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_loan_manager(&env)?;
+        Self::require_not_paused(&env, PauseScope::Borrows)?;
 
-        assert!(amount > 0, "Amount must be positive");
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
         
         // Check available liquidity
         let total_liquidity: i128 = env.storage().instance().get(&DataKey::TotalLiquidity).unwrap_or(0);
         let total_borrowed: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap_or(0);
         let available = total_liquidity - total_borrowed;
         
-        assert!(amount <= available, "Insufficient liquidity in the pool");
+        if amount > available {
+            return Err(Error::InsufficientLiquidity);
+        }
+
+        // Keep enough idle liquidity for lenders to withdraw
+        if amount > Self::max_borrowable(env.clone()) {
+            return Err(Error::UtilizationCapExceeded);
+        }
         
         // Update total borrowed
         env.storage().instance().set(&DataKey::TotalBorrowed, &(total_borrowed + amount));
@@ -209,80 +559,239 @@ impl LendingPool {
         let usdc_token = token::Client::new(&env, &usdc_token_address);
         usdc_token.transfer(&env.current_contract_address(), &borrower, &amount);
         
-        env.events().publish(("borrow", loan_id, borrower), amount);
+        BorrowEvent { loan_id, borrower, amount }.publish(&env);
+
+        Ok(())
     }
     
     // Repay to pool (called by LoanManager only)
-    pub fn repay(env: Env, principal: i128, interest: i128, loan_id: u64) {
-        // Only loan manager should call this. See note in `borrow`.
-        let _loan_manager: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::LoanManagerAddress)
-            .expect("Loan manager not configured");
+    pub fn repay(env: Env, principal: i128, interest: i128, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_loan_manager(&env)?;
 
-        assert!(principal >= 0 && interest >= 0, "Amounts must be non-negative");
-        
         let total_borrowed: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap_or(0);
+        if principal < 0 || interest < 0 || principal > total_borrowed {
+            return Err(Error::InvalidAmount);
+        }
+
         let total_interest_earned: i128 = env.storage().instance().get(&DataKey::TotalInterestEarned).unwrap_or(0);
         
         // Update totals
         env.storage().instance().set(&DataKey::TotalBorrowed, &(total_borrowed - principal));
         env.storage().instance().set(&DataKey::TotalInterestEarned, &(total_interest_earned + interest));
         
-        // Distribute interest to lenders proportionally
+        // Distribute interest to current shares; lenders claim it via `claim_interest`
         if interest > 0 {
-            let total_liquidity: i128 = env.storage().instance().get(&DataKey::TotalLiquidity).unwrap_or(1);
-            
-            if total_liquidity > 0 {
-                // In a real implementation, you would typically have a way to iterate through all lenders
-                // For this example, we'll store the total interest and distribute it when lenders withdraw
-                // or provide a separate claim function
-                
-                // Store the interest to be distributed
-                let interest_per_share = (interest as u128).checked_mul(1_000_000_000)  // For better precision
-                    .map(|v| v / (total_liquidity as u128))
-                    .unwrap_or(0) as i128;
-                
-                // Store the current interest per share
-                let mut current_interest_per_share: i128 = env.storage().instance()
-                    .get(&DataKey::AccumulatedInterestPerShare)
-                    .unwrap_or(0);
-                    
-                current_interest_per_share += interest_per_share;
-                env.storage().instance().set(
-                    &DataKey::AccumulatedInterestPerShare,
-                    &current_interest_per_share
-                );
-                
-                // The actual interest will be calculated when lenders withdraw or claim
-                // by comparing their last claimed interest per share with the current one
-            }
+            Self::distribute_interest(&env, interest);
         }
-        env.events().publish(("repay", loan_id), principal + interest);
+
+        RepayEvent { loan_id, amount: principal + interest }.publish(&env);
+
+        Ok(())
     }
     
+    // Write off unrecoverable principal of a defaulted loan (called by LoanManager only).
+    // The loss comes out of total assets, so every share is worth proportionally less.
+    pub fn write_off(env: Env, principal: i128, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_loan_manager(&env)?;
+
+        let total_borrowed: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap_or(0);
+        if principal < 0 || principal > total_borrowed {
+            return Err(Error::InvalidAmount);
+        }
+
+        let total_liquidity = Self::total_assets(env.clone());
+        let total_losses = Self::total_losses(env.clone());
+
+        env.storage().instance().set(&DataKey::TotalBorrowed, &(total_borrowed - principal));
+        env.storage().instance().set(&DataKey::TotalLiquidity, &(total_liquidity - principal));
+        env.storage().instance().set(&DataKey::TotalLosses, &(total_losses + principal));
+
+        WriteOffEvent { loan_id, amount: principal }.publish(&env);
+
+        Ok(())
+    }
+
+    // Book collateral sale proceeds already transferred to the pool (called by LoanManager only).
+    // They return to total assets, raising the share price back toward its pre-default level.
+    pub fn recover(env: Env, amount: i128, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_loan_manager(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let total_liquidity = Self::total_assets(env.clone());
+        let total_recovered = Self::total_recovered(env.clone());
+
+        env.storage().instance().set(&DataKey::TotalLiquidity, &(total_liquidity + amount));
+        env.storage().instance().set(&DataKey::TotalRecovered, &(total_recovered + amount));
+
+        RecoverEvent { loan_id, amount }.publish(&env);
+
+        Ok(())
+    }
+
+    // Collateral sale proceeds returned to the pool over its lifetime
+    pub fn total_recovered(env: Env) -> i128 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::TotalRecovered).unwrap_or(0)
+    }
+
+    // Principal lost to defaulted loans over the pool's lifetime
+    pub fn total_losses(env: Env) -> i128 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::TotalLosses).unwrap_or(0)
+    }
+
+    // Lender claims all interest accrued on their shares
+    pub fn claim_interest(env: Env, lender: Address) -> Result<i128, Error> {
+        storage::extend_instance(&env);
+
+        lender.require_auth();
+
+        let mut lender_info: LenderInfo = Self::read_lender(&env, &lender)
+            .ok_or(Error::LenderNotFound)?;
+
+        let acc_interest = Self::accumulated_interest_per_share(&env);
+        Self::accrue_interest(&mut lender_info, acc_interest);
+
+        let amount = lender_info.unclaimed_interest;
+        lender_info.unclaimed_interest = 0;
+        lender_info.earned_interest += amount;
+
+        storage::write(&env, &DataKey::LenderInfo(lender.clone()), &lender_info);
+
+        if amount > 0 {
+            let usdc_token_address: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
+            let usdc_token = token::Client::new(&env, &usdc_token_address);
+            usdc_token.transfer(&env.current_contract_address(), &lender, &amount);
+
+            let total_claimed: i128 = env.storage().instance()
+                .get(&DataKey::TotalInterestClaimed)
+                .unwrap_or(0);
+            env.storage().instance().set(&DataKey::TotalInterestClaimed, &(total_claimed + amount));
+
+            ClaimInterestEvent { lender, amount }.publish(&env);
+        }
+
+        Ok(amount)
+    }
+
+    // Interest the lender could claim right now
+    pub fn pending_interest(env: Env, lender: Address) -> i128 {
+        storage::extend_instance(&env);
+
+        let lender_info: Option<LenderInfo> = Self::read_lender(&env, &lender);
+
+        match lender_info {
+            Some(mut lender_info) => {
+                let acc_interest = Self::accumulated_interest_per_share(&env);
+                Self::accrue_interest(&mut lender_info, acc_interest);
+                lender_info.unclaimed_interest
+            }
+            None => 0,
+        }
+    }
+
     // Get available liquidity
     pub fn get_available_liquidity(env: Env) -> i128 {
+        storage::extend_instance(&env);
+
         let total_liquidity: i128 = env.storage().instance().get(&DataKey::TotalLiquidity).unwrap_or(0);
         let total_borrowed: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap_or(0);
         total_liquidity - total_borrowed
     }
     
-    // Get lender info
+    // Get lender info, with the share percentage computed against current total shares
     pub fn get_lender_info(env: Env, lender: Address) -> LenderInfo {
-        env.storage().persistent()
-            .get(&DataKey::LenderInfo(lender))
-            .unwrap_or_else(|| LenderInfo {
+        storage::extend_instance(&env);
+
+        let mut lender_info = Self::read_lender(&env, &lender)
+            .unwrap_or(LenderInfo {
                 deposit_amount: 0,
                 deposit_timestamp: 0,
                 earned_interest: 0,
+                shares: 0,
+                reward_debt: 0,
+                unclaimed_interest: 0,
                 share_percentage: 0,
-            })
+            });
+
+        let total_shares = Self::total_shares(env);
+        lender_info.share_percentage = if total_shares > 0 {
+            ((lender_info.shares as u128 * 10000) / total_shares as u128) as u32
+        } else {
+            0
+        };
+
+        lender_info
+    }
+
+    // Principal backing the shares: idle liquidity plus outstanding loans, net of losses.
+    // Interest is paid out through `claim_interest` and is not part of it.
+    pub fn total_assets(env: Env) -> i128 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::TotalLiquidity).unwrap_or(0)
+    }
+
+    // Total pool shares held by all lenders
+    pub fn total_shares(env: Env) -> i128 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::TotalShares).unwrap_or(0)
+    }
+
+    // Shares minted for depositing `assets` at the current share price
+    pub fn preview_deposit(env: Env, assets: i128) -> i128 {
+        storage::extend_instance(&env);
+
+        Self::convert_to_shares(env, assets)
+    }
+
+    // Shares burned for withdrawing `assets` at the current share price (rounded up)
+    pub fn preview_withdraw(env: Env, assets: i128) -> i128 {
+        storage::extend_instance(&env);
+
+        let total_assets = Self::total_assets(env.clone()) + VIRTUAL_ASSETS;
+        let total_shares = Self::total_shares(env) + VIRTUAL_SHARES;
+
+        (assets * total_shares + total_assets - 1) / total_assets
+    }
+
+    // Shares worth `assets` at the current share price (rounded down)
+    pub fn convert_to_shares(env: Env, assets: i128) -> i128 {
+        storage::extend_instance(&env);
+
+        let total_assets = Self::total_assets(env.clone()) + VIRTUAL_ASSETS;
+        let total_shares = Self::total_shares(env) + VIRTUAL_SHARES;
+
+        (assets * total_shares) / total_assets
+    }
+
+    // Principal value of `shares` at the current share price (rounded down); interest on
+    // them is reported separately by `pending_interest`
+    pub fn convert_to_assets(env: Env, shares: i128) -> i128 {
+        storage::extend_instance(&env);
+
+        let total_assets = Self::total_assets(env.clone()) + VIRTUAL_ASSETS;
+        let total_shares = Self::total_shares(env) + VIRTUAL_SHARES;
+
+        (shares * total_assets) / total_shares
     }
     
     // Get utilization rate
     pub fn get_utilization_rate(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
         let total_liquidity: i128 = env.storage().instance().get(&DataKey::TotalLiquidity).unwrap_or(0);
         let total_borrowed: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap_or(0);
         
@@ -292,4 +801,247 @@ impl LendingPool {
         
         ((total_borrowed as u128 * 10000) / total_liquidity as u128) as u32
     }
-}
\ No newline at end of file
+
+    // Largest loan the pool can fund without exceeding its utilization cap
+    pub fn max_borrowable(env: Env) -> i128 {
+        storage::extend_instance(&env);
+
+        let total_liquidity: i128 = env.storage().instance().get(&DataKey::TotalLiquidity).unwrap_or(0);
+        let total_borrowed: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap_or(0);
+        let max_utilization = Self::get_max_utilization(env);
+
+        let borrow_limit = (total_liquidity * max_utilization as i128) / 10000;
+        (borrow_limit - total_borrowed).max(0)
+    }
+
+    pub fn get_max_utilization(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::MaxUtilization).unwrap_or(9000)
+    }
+
+    // Admin updates the utilization cap enforced on borrowing
+    pub fn set_max_utilization(env: Env, caller: Address, max_utilization: u32) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::RiskManager, &caller)?;
+
+        if !(MIN_UTILIZATION_CAP..=MAX_UTILIZATION_CAP).contains(&max_utilization) {
+            return Err(Error::InvalidUtilizationCap);
+        }
+
+        env.storage().instance().set(&DataKey::MaxUtilization, &max_utilization);
+        Ok(())
+    }
+
+    // Admin updates the utilization-based rate model
+    pub fn set_rate_model(env: Env, caller: Address, model: RateModel) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::RiskManager, &caller)?;
+
+        if model.optimal_utilization == 0 || model.optimal_utilization >= 10000 {
+            return Err(Error::InvalidRateModel);
+        }
+        // Summed in u64 so oversized parameters are rejected instead of overflowing
+        let max_rate = model.base_rate as u64 + model.slope_low as u64 + model.slope_high as u64;
+        if max_rate > MAX_BORROW_RATE {
+            return Err(Error::InvalidRateModel);
+        }
+
+        env.storage().instance().set(&DataKey::RateModel, &model);
+        Ok(())
+    }
+
+    pub fn get_rate_model(env: Env) -> Result<RateModel, Error> {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::RateModel).ok_or(Error::NotInitialized)
+    }
+
+    // Borrow APR (basis points) for a new loan of `amount`, priced at the utilization
+    // the pool would reach after funding it. Pass 0 for the current rate.
+    pub fn get_borrow_rate(env: Env, amount: i128) -> Result<u32, Error> {
+        storage::extend_instance(&env);
+
+        let model = Self::get_rate_model(env.clone())?;
+
+        let total_liquidity = Self::total_assets(env.clone());
+        let total_borrowed: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap_or(0);
+        let borrowed = total_borrowed + amount.max(0);
+
+        let utilization = if total_liquidity <= 0 {
+            if borrowed > 0 { 10000 } else { 0 }
+        } else {
+            ((borrowed as u128 * 10000) / total_liquidity as u128).min(10000) as u32
+        };
+
+        Ok(Self::rate_at_utilization(&model, utilization))
+    }
+
+    // Anyone can extend the TTL of persistent entries, e.g. a keeper keeping live data
+    // from being archived. Returns how many of `keys` exist.
+    pub fn bump(env: Env, keys: Vec<DataKey>) -> u32 {
+        storage::extend_instance(&env);
+
+        let mut extended = 0;
+        for key in keys.iter() {
+            if storage::extend(&env, &key) {
+                extended += 1;
+            }
+        }
+        extended
+    }
+
+    // Internal: Fail while `scope` is paused
+    fn require_not_paused(env: &Env, scope: PauseScope) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Paused(scope)) {
+            return Err(Error::Paused);
+        }
+        Ok(())
+    }
+
+    // Internal: Kinked rate curve, gentle up to the optimal utilization and steep above it
+    fn rate_at_utilization(model: &RateModel, utilization: u32) -> u32 {
+        let base_rate = model.base_rate as u64;
+        let slope_low = model.slope_low as u64;
+        let slope_high = model.slope_high as u64;
+        let optimal = model.optimal_utilization as u64;
+        let utilization = utilization as u64;
+
+        let rate = if utilization <= optimal {
+            base_rate + (slope_low * utilization) / optimal
+        } else {
+            base_rate + slope_low + (slope_high * (utilization - optimal)) / (10000 - optimal)
+        };
+        rate.min(MAX_BORROW_RATE) as u32
+    }
+
+    // Internal: Current interest per share, scaled by INTEREST_PRECISION
+    fn accumulated_interest_per_share(env: &Env) -> i128 {
+        env.storage().instance().get(&DataKey::AccumulatedInterestPerShare).unwrap_or(0)
+    }
+
+    // Internal: Credit `interest` to the outstanding shares. With none outstanding it is held
+    // for the next depositor rather than added to total assets, where the virtual share would
+    // take a cut of it.
+    fn distribute_interest(env: &Env, interest: i128) {
+        let total_shares = Self::total_shares(env.clone());
+
+        if total_shares > 0 {
+            let acc_interest = Self::accumulated_interest_per_share(env)
+                + (interest * INTEREST_PRECISION) / total_shares;
+            env.storage().instance().set(&DataKey::AccumulatedInterestPerShare, &acc_interest);
+        } else {
+            let held_interest: i128 = env.storage().instance().get(&DataKey::HeldInterest).unwrap_or(0);
+            env.storage().instance().set(&DataKey::HeldInterest, &(held_interest + interest));
+        }
+    }
+
+    // Internal: Bank interest accrued since the lender's last checkpoint
+    fn accrue_interest(lender_info: &mut LenderInfo, acc_interest: i128) {
+        let accrued = (lender_info.shares * acc_interest) / INTEREST_PRECISION;
+        if accrued > lender_info.reward_debt {
+            lender_info.unclaimed_interest += accrued - lender_info.reward_debt;
+            lender_info.reward_debt = accrued;
+        }
+    }
+
+    // Internal: Checkpoint after the share balance changed, rounding up so that
+    // lenders can never claim more interest than was distributed
+    fn reset_reward_debt(lender_info: &mut LenderInfo, acc_interest: i128) {
+        lender_info.reward_debt =
+            (lender_info.shares * acc_interest + INTEREST_PRECISION - 1) / INTEREST_PRECISION;
+    }
+
+    // Internal: Rate curve a new pool starts with
+    fn default_rate_model(base_rate: u32) -> RateModel {
+        RateModel {
+            base_rate,
+            slope_low: 400,
+            slope_high: 6000,
+            optimal_utilization: 8000,
+        }
+    }
+
+    // Internal: Convert the original pool's flat rate and totals. Deposits were always worth
+    // face value, so they become shares 1:1, and the accumulator restarts for them. Lender
+    // entries can't be listed and convert on first use, paid from the interest held here.
+    fn migrate_to_v1(env: &Env) {
+        let base_rate: Option<u32> = env.storage().instance().get(&DataKey::BaseInterestRate);
+        let Some(base_rate) = base_rate else {
+            return;
+        };
+        env.storage().instance().remove(&DataKey::BaseInterestRate);
+        env.storage().instance().set(&DataKey::RateModel, &Self::default_rate_model(base_rate));
+
+        let total_liquidity = Self::total_assets(env.clone());
+        env.storage().instance().set(&DataKey::TotalShares, &total_liquidity);
+
+        let legacy_interest = Self::accumulated_interest_per_share(env);
+        env.storage().instance().set(&DataKey::LegacyInterestPerShare, &legacy_interest);
+
+        // Interest received and not yet paid out, as booked and as actually in the pool
+        let total_borrowed: i128 = env.storage().instance().get(&DataKey::TotalBorrowed).unwrap_or(0);
+        let total_interest_earned: i128 = env.storage().instance().get(&DataKey::TotalInterestEarned).unwrap_or(0);
+        let usdc_token_address: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
+        let balance = token::Client::new(env, &usdc_token_address).balance(&env.current_contract_address());
+        let held_interest = total_interest_earned.min(balance - (total_liquidity - total_borrowed)).max(0);
+
+        if total_liquidity > 0 {
+            env.storage().instance().set(&DataKey::LegacyInterestHeld, &held_interest);
+        } else {
+            env.storage().instance().set(&DataKey::HeldInterest, &held_interest);
+        }
+        env.storage().instance().set(&DataKey::AccumulatedInterestPerShare, &0i128);
+    }
+
+    // Internal: The lender's position, converting one the original pool wrote. Its deposit
+    // becomes shares, and interest it is still owed stays claimable.
+    fn read_lender(env: &Env, lender: &Address) -> Option<LenderInfo> {
+        let key = DataKey::LenderInfo(lender.clone());
+        let value: Val = storage::read(env, &key)?;
+        if storage::has_field(env, &value, "shares") {
+            return LenderInfo::try_from_val(env, &value).ok();
+        }
+        let legacy = LegacyLenderInfo::try_from_val(env, &value).ok()?;
+
+        // The original formula credited every deposit with all interest booked before it, so
+        // it overstates late depositors. Paying it only out of interest the pool still holds
+        // keeps lenders from claiming more than borrowers paid.
+        let legacy_interest: i128 = env.storage().instance().get(&DataKey::LegacyInterestPerShare).unwrap_or(0);
+        let held_interest: i128 = env.storage().instance().get(&DataKey::LegacyInterestHeld).unwrap_or(0);
+        let legacy_owed = (legacy.deposit_amount * legacy_interest) / LEGACY_INTEREST_PRECISION
+            - legacy.earned_interest;
+        let owed = legacy_owed.min(held_interest).max(0);
+        env.storage().instance().set(&DataKey::LegacyInterestHeld, &(held_interest - owed));
+
+        // The shares were outstanding since the accumulator restarted, so they checkpoint at zero
+        let lender_info = LenderInfo {
+            deposit_amount: legacy.deposit_amount,
+            deposit_timestamp: legacy.deposit_timestamp,
+            earned_interest: legacy.earned_interest,
+            shares: legacy.deposit_amount,
+            reward_debt: 0,
+            unclaimed_interest: owed,
+            share_percentage: 0,
+        };
+
+        storage::write(env, &key, &lender_info);
+        Some(lender_info)
+    }
+
+    // Internal: Require auth from the configured LoanManager contract
+    fn require_loan_manager(env: &Env) -> Result<(), Error> {
+        let loan_manager: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LoanManagerAddress)
+            .ok_or(Error::NotInitialized)?;
+        loan_manager.require_auth();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
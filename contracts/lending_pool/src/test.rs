@@ -0,0 +1,509 @@
+use super::*;
+use soroban_sdk::{
+    testutils::{Address as _, MockAuth, MockAuthInvoke},
+    token::{StellarAssetClient, TokenClient},
+    IntoVal,
+};
+use proptest::prelude::*;
+
+struct Setup<'a> {
+    env: Env,
+    pool: LendingPoolClient<'a>,
+    token: TokenClient<'a>,
+    token_admin: StellarAssetClient<'a>,
+    admin: Address,
+    loan_manager: Address,
+    lender: Address,
+    borrower: Address,
+}
+
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let loan_manager = Address::generate(&env);
+    let lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let token = TokenClient::new(&env, &usdc.address());
+    let token_admin = StellarAssetClient::new(&env, &usdc.address());
+    token_admin.mint(&lender, &10_000);
+
+    let pool_id = env.register(LendingPool, ());
+    let pool = LendingPoolClient::new(&env, &pool_id);
+    pool.initialize(&admin, &loan_manager, &usdc.address(), &500);
+    pool.deposit(&lender, &10_000);
+
+    Setup { env, pool, token, token_admin, admin, loan_manager, lender, borrower }
+}
+
+fn mock_auth(s: &Setup, signer: &Address, fn_name: &str, args: soroban_sdk::Vec<soroban_sdk::Val>) {
+    s.env.mock_auths(&[MockAuth {
+        address: signer,
+        invoke: &MockAuthInvoke {
+            contract: &s.pool.address,
+            fn_name,
+            args,
+            sub_invokes: &[],
+        },
+    }]);
+}
+
+#[test]
+fn test_loan_manager_can_borrow_and_repay() {
+    let s = setup();
+
+    mock_auth(&s, &s.loan_manager, "borrow", (4_000i128, s.borrower.clone(), 1u64).into_val(&s.env));
+    s.pool.borrow(&4_000, &s.borrower, &1);
+
+    assert_eq!(s.token.balance(&s.borrower), 4_000);
+    assert_eq!(s.pool.get_available_liquidity(), 6_000);
+
+    mock_auth(&s, &s.loan_manager, "repay", (4_000i128, 100i128, 1u64).into_val(&s.env));
+    s.pool.repay(&4_000, &100, &1);
+
+    assert_eq!(s.pool.get_available_liquidity(), 10_000);
+    assert_eq!(s.pool.pending_interest(&s.lender), 100);
+}
+
+#[test]
+fn test_third_party_cannot_borrow() {
+    let s = setup();
+    let attacker = Address::generate(&s.env);
+
+    mock_auth(&s, &attacker, "borrow", (4_000i128, attacker.clone(), 1u64).into_val(&s.env));
+    assert!(s.pool.try_borrow(&4_000, &attacker, &1).is_err());
+
+    assert_eq!(s.token.balance(&attacker), 0);
+    assert_eq!(s.pool.get_available_liquidity(), 10_000);
+}
+
+#[test]
+fn test_borrower_cannot_borrow_directly() {
+    let s = setup();
+
+    mock_auth(&s, &s.borrower, "borrow", (4_000i128, s.borrower.clone(), 1u64).into_val(&s.env));
+    assert!(s.pool.try_borrow(&4_000, &s.borrower, &1).is_err());
+
+    assert_eq!(s.token.balance(&s.borrower), 0);
+    assert_eq!(s.pool.get_available_liquidity(), 10_000);
+}
+
+#[test]
+fn test_third_party_cannot_repay() {
+    let s = setup();
+    let attacker = Address::generate(&s.env);
+
+    mock_auth(&s, &attacker, "repay", (0i128, 1_000_000i128, 1u64).into_val(&s.env));
+    assert!(s.pool.try_repay(&0, &1_000_000, &1).is_err());
+
+    assert_eq!(s.pool.pending_interest(&s.lender), 0);
+}
+
+#[test]
+fn test_pool_errors() {
+    let s = setup();
+    let stranger = Address::generate(&s.env);
+    s.env.mock_all_auths();
+
+    assert_eq!(s.pool.try_deposit(&stranger, &0), Err(Ok(Error::InvalidAmount)));
+    assert_eq!(s.pool.try_withdraw(&stranger, &100), Err(Ok(Error::LenderNotFound)));
+    assert_eq!(
+        s.pool.try_borrow(&10_001, &s.borrower, &1),
+        Err(Ok(Error::InsufficientLiquidity))
+    );
+    assert_eq!(s.pool.try_repay(&-1, &0, &1), Err(Ok(Error::InvalidAmount)));
+
+    // Principal can't come back that was never lent out
+    s.pool.borrow(&4_000, &s.borrower, &1);
+    assert_eq!(s.pool.try_repay(&4_001, &0, &1), Err(Ok(Error::InvalidAmount)));
+    assert_eq!(s.pool.try_write_off(&4_001, &1), Err(Ok(Error::InvalidAmount)));
+    assert_eq!(s.pool.get_utilization_rate(), 4_000);
+}
+
+// Books `interest` on a loan of `principal`, as LoanManager would after a repayment
+fn book_interest(s: &Setup, principal: i128, interest: i128) {
+    s.env.mock_all_auths();
+    s.pool.borrow(&principal, &s.borrower, &1);
+    s.token_admin.mint(&s.pool.address, &(principal + interest));
+    s.pool.repay(&principal, &interest, &1);
+}
+
+#[test]
+fn test_shares_track_deposits() {
+    let s = setup();
+    let late_lender = Address::generate(&s.env);
+    s.token_admin.mint(&late_lender, &30_000);
+
+    assert_eq!(s.pool.get_lender_info(&s.lender).shares, 10_000);
+    assert_eq!(s.pool.get_lender_info(&s.lender).share_percentage, 10_000);
+
+    assert_eq!(s.pool.preview_deposit(&30_000), 30_000);
+    s.pool.deposit(&late_lender, &30_000);
+
+    assert_eq!(s.pool.total_shares(), 40_000);
+    assert_eq!(s.pool.total_assets(), 40_000);
+
+    // Both percentages reflect the latest deposit
+    assert_eq!(s.pool.get_lender_info(&s.lender).share_percentage, 2_500);
+    assert_eq!(s.pool.get_lender_info(&late_lender).share_percentage, 7_500);
+
+    s.pool.withdraw(&late_lender, &10_000);
+    assert_eq!(s.pool.get_lender_info(&s.lender).share_percentage, 3_333);
+    assert_eq!(s.pool.convert_to_assets(&s.pool.get_lender_info(&late_lender).shares), 20_000);
+}
+
+#[test]
+fn test_late_lender_earns_no_past_interest() {
+    let s = setup();
+    let late_lender = Address::generate(&s.env);
+    s.token_admin.mint(&late_lender, &10_000);
+
+    book_interest(&s, 4_000, 1_000);
+    s.pool.deposit(&late_lender, &10_000);
+
+    assert_eq!(s.pool.pending_interest(&s.lender), 1_000);
+    assert_eq!(s.pool.pending_interest(&late_lender), 0);
+
+    book_interest(&s, 4_000, 500);
+
+    assert_eq!(s.pool.pending_interest(&s.lender), 1_250);
+    assert_eq!(s.pool.pending_interest(&late_lender), 250);
+}
+
+#[test]
+fn test_interest_repaid_to_an_empty_pool_goes_to_the_next_depositor() {
+    let s = setup();
+    let next_lender = Address::generate(&s.env);
+    s.token_admin.mint(&next_lender, &10_000);
+    s.env.mock_all_auths();
+
+    // The only lender leaves before the last interest payment comes in
+    s.pool.borrow(&4_000, &s.borrower, &1);
+    s.token_admin.mint(&s.pool.address, &4_000);
+    s.pool.repay(&4_000, &0, &1);
+    s.pool.withdraw(&s.lender, &10_000);
+    s.token_admin.mint(&s.pool.address, &500);
+    s.pool.repay(&0, &500, &1);
+
+    // The interest stays out of the share price
+    assert_eq!(s.pool.total_assets(), 0);
+    assert_eq!(s.pool.total_shares(), 0);
+
+    s.pool.deposit(&next_lender, &10_000);
+    assert_eq!(s.pool.convert_to_assets(&s.pool.get_lender_info(&next_lender).shares), 10_000);
+    assert_eq!(s.pool.pending_interest(&next_lender), 500);
+    assert_eq!(s.pool.claim_interest(&next_lender), 500);
+}
+
+#[test]
+fn test_interest_stays_out_of_share_price() {
+    let s = setup();
+
+    book_interest(&s, 4_000, 1_000);
+
+    // Interest is owed through the reward-debt checkpoint, not the share price
+    assert_eq!(s.pool.total_assets(), 10_000);
+    assert_eq!(s.pool.convert_to_assets(&10_000), 10_000);
+    assert_eq!(s.pool.preview_deposit(&5_000), 5_000);
+    assert_eq!(s.pool.preview_withdraw(&5_000), 5_000);
+    assert_eq!(s.pool.pending_interest(&s.lender), 1_000);
+
+    // Losses move the share price but leave earned interest alone
+    s.pool.borrow(&2_000, &s.borrower, &2);
+    s.pool.write_off(&2_000, &2);
+    assert_eq!(s.pool.convert_to_assets(&10_000), 8_000);
+    assert_eq!(s.pool.pending_interest(&s.lender), 1_000);
+}
+
+#[test]
+fn test_migrate_pays_legacy_interest_only_from_what_the_pool_holds() {
+    let s = setup();
+    let lender_b = Address::generate(&s.env);
+    s.token_admin.mint(&lender_b, &10_000);
+    s.pool.deposit(&lender_b, &10_000);
+    s.pool.withdraw(&s.lender, &5_000);
+    s.token_admin.mint(&s.pool.address, &100);
+
+    // The original pool booked 200 over 20_000 deposited; the first lender then took out half
+    // its deposit and its 100 of interest, leaving the other 100 in the pool
+    s.env.as_contract(&s.pool.address, || {
+        let instance = s.env.storage().instance();
+        instance.remove(&DataKey::SchemaVersion);
+        instance.remove(&DataKey::RateModel);
+        instance.remove(&DataKey::TotalShares);
+        instance.set(&DataKey::BaseInterestRate, &500u32);
+        instance.set(&DataKey::TotalInterestEarned, &100i128);
+        instance.set(&DataKey::AccumulatedInterestPerShare, &(200 * LEGACY_INTEREST_PRECISION / 20_000));
+        let legacy_a = LegacyLenderInfo {
+            deposit_amount: 5_000,
+            deposit_timestamp: 0,
+            earned_interest: 100,
+            share_percentage: 0,
+        };
+        let legacy_b = LegacyLenderInfo { deposit_amount: 10_000, earned_interest: 0, ..legacy_a.clone() };
+        storage::write(&s.env, &DataKey::LenderInfo(s.lender.clone()), &legacy_a);
+        storage::write(&s.env, &DataKey::LenderInfo(lender_b.clone()), &legacy_b);
+    });
+
+    assert_eq!(s.pool.migrate(), 1);
+    assert_eq!(s.pool.total_shares(), 15_000);
+
+    // The first lender was already paid; the rest of what is held goes to the other
+    assert_eq!(s.pool.pending_interest(&s.lender), 0);
+    assert_eq!(s.pool.pending_interest(&lender_b), 100);
+
+    book_interest(&s, 3_000, 150);
+    assert_eq!(s.pool.pending_interest(&s.lender), 50);
+    assert_eq!(s.pool.claim_interest(&lender_b), 200);
+}
+
+#[test]
+fn test_claim_interest() {
+    let s = setup();
+
+    book_interest(&s, 4_000, 1_000);
+
+    // Withdrawing principal checkpoints interest instead of paying it
+    s.pool.withdraw(&s.lender, &5_000);
+    assert_eq!(s.token.balance(&s.lender), 5_000);
+    assert_eq!(s.pool.pending_interest(&s.lender), 1_000);
+
+    book_interest(&s, 1_000, 100);
+    assert_eq!(s.pool.pending_interest(&s.lender), 1_100);
+
+    assert_eq!(s.pool.claim_interest(&s.lender), 1_100);
+    assert_eq!(s.token.balance(&s.lender), 6_100);
+    assert_eq!(s.pool.pending_interest(&s.lender), 0);
+    assert_eq!(s.pool.claim_interest(&s.lender), 0);
+
+    let info = s.pool.get_lender_info(&s.lender);
+    assert_eq!(info.earned_interest, 1_100);
+    assert_eq!(info.deposit_amount, 5_000);
+    assert_eq!(info.shares, 5_000);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn prop_claimed_interest_never_exceeds_repaid(
+        ops in prop::collection::vec((0u8..4, 0usize..3, 1i128..50_000), 1..40)
+    ) {
+        let s = setup();
+        s.env.mock_all_auths();
+
+        let lenders = [
+            s.lender.clone(),
+            Address::generate(&s.env),
+            Address::generate(&s.env),
+        ];
+        for lender in lenders.iter() {
+            s.token_admin.mint(lender, &1_000_000);
+        }
+
+        let mut total_repaid_interest = 0i128;
+        let mut total_claimed = 0i128;
+
+        for (op, who, amount) in ops {
+            let lender = &lenders[who];
+            match op {
+                0 => {
+                    s.pool.deposit(lender, &amount);
+                }
+                1 => {
+                    let _ = s.pool.try_withdraw(lender, &amount);
+                }
+                2 => {
+                    let principal = amount.min(s.pool.max_borrowable());
+                    let interest = amount / 7 + 1;
+                    if principal > 0 {
+                        book_interest(&s, principal, interest);
+                        total_repaid_interest += interest;
+                    }
+                }
+                _ => {
+                    if let Ok(Ok(claimed)) = s.pool.try_claim_interest(lender) {
+                        total_claimed += claimed;
+                    }
+                }
+            }
+
+            let pending: i128 = lenders.iter().map(|l| s.pool.pending_interest(l)).sum();
+            prop_assert!(total_claimed + pending <= total_repaid_interest);
+        }
+    }
+}
+
+#[test]
+fn test_kinked_rate_model() {
+    let s = setup();
+    s.env.mock_all_auths();
+
+    // 500 base, +400 up to 80% utilization, +6000 from 80% to 100%
+    assert_eq!(s.pool.get_borrow_rate(&0), 500);
+    assert_eq!(s.pool.get_borrow_rate(&4_000), 700);
+    assert_eq!(s.pool.get_borrow_rate(&8_000), 900);
+    assert_eq!(s.pool.get_borrow_rate(&9_000), 3_900);
+    assert_eq!(s.pool.get_borrow_rate(&10_000), 6_900);
+
+    s.pool.borrow(&4_000, &s.borrower, &1);
+    assert_eq!(s.pool.get_utilization_rate(), 4_000);
+    assert_eq!(s.pool.get_borrow_rate(&0), 700);
+    assert_eq!(s.pool.get_borrow_rate(&5_000), 3_900);
+
+    let model = RateModel { base_rate: 200, slope_low: 800, slope_high: 4_000, optimal_utilization: 5_000 };
+    s.pool.set_rate_model(&s.admin, &model);
+    assert_eq!(s.pool.get_rate_model(), model);
+    assert_eq!(s.pool.get_borrow_rate(&0), 840);
+    assert_eq!(s.pool.get_borrow_rate(&3_000), 2_600);
+
+    let bad = RateModel { optimal_utilization: 10_000, ..model };
+    assert_eq!(s.pool.try_set_rate_model(&s.admin, &bad), Err(Ok(Error::InvalidRateModel)));
+
+    // Parameters whose sum overflows u32 are rejected rather than wrapping past the bound
+    let overflowing = RateModel { base_rate: u32::MAX, slope_low: 1, ..model };
+    assert_eq!(s.pool.try_set_rate_model(&s.admin, &overflowing), Err(Ok(Error::InvalidRateModel)));
+    let too_steep = RateModel { slope_high: 100_000, ..model };
+    assert_eq!(s.pool.try_set_rate_model(&s.admin, &too_steep), Err(Ok(Error::InvalidRateModel)));
+    assert_eq!(s.pool.get_rate_model(), model);
+}
+
+#[test]
+fn test_borrow_respects_utilization_cap() {
+    let s = setup();
+    s.env.mock_all_auths();
+
+    // Default cap is 90% of the 10_000 deposited
+    assert_eq!(s.pool.get_max_utilization(), 9_000);
+    assert_eq!(s.pool.max_borrowable(), 9_000);
+    assert_eq!(
+        s.pool.try_borrow(&9_001, &s.borrower, &1),
+        Err(Ok(Error::UtilizationCapExceeded))
+    );
+
+    s.pool.borrow(&6_000, &s.borrower, &1);
+    assert_eq!(s.pool.max_borrowable(), 3_000);
+
+    s.pool.set_max_utilization(&s.admin, &5_000);
+    assert_eq!(s.pool.max_borrowable(), 0);
+    assert_eq!(
+        s.pool.try_borrow(&1, &s.borrower, &2),
+        Err(Ok(Error::UtilizationCapExceeded))
+    );
+
+    assert_eq!(s.pool.try_set_max_utilization(&s.admin, &4_999), Err(Ok(Error::InvalidUtilizationCap)));
+    assert_eq!(s.pool.try_set_max_utilization(&s.admin, &10_000), Err(Ok(Error::InvalidUtilizationCap)));
+    assert_eq!(s.pool.get_max_utilization(), 5_000);
+}
+
+#[test]
+fn test_risk_manager_sets_pool_parameters() {
+    let s = setup();
+    let risk_manager = Address::generate(&s.env);
+
+    assert_eq!(s.pool.try_set_max_utilization(&risk_manager, &8_000), Err(Ok(Error::Unauthorized)));
+
+    s.pool.grant_role(&Role::RiskManager, &risk_manager);
+    s.pool.set_max_utilization(&risk_manager, &8_000);
+    assert_eq!(s.pool.get_max_utilization(), 8_000);
+
+    let model = RateModel { base_rate: 300, ..s.pool.get_rate_model() };
+    s.pool.set_rate_model(&risk_manager, &model);
+    assert_eq!(s.pool.get_rate_model(), model);
+}
+
+#[test]
+fn test_write_off_reduces_share_price() {
+    let s = setup();
+    let late_lender = Address::generate(&s.env);
+    s.token_admin.mint(&late_lender, &6_000);
+    s.env.mock_all_auths();
+
+    s.pool.borrow(&4_000, &s.borrower, &1);
+
+    mock_auth(&s, &s.borrower, "write_off", (4_000i128, 1u64).into_val(&s.env));
+    assert!(s.pool.try_write_off(&4_000, &1).is_err());
+
+    mock_auth(&s, &s.loan_manager, "write_off", (4_000i128, 1u64).into_val(&s.env));
+    s.pool.write_off(&4_000, &1);
+
+    assert_eq!(s.pool.total_losses(), 4_000);
+    assert_eq!(s.pool.total_assets(), 6_000);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+    assert_eq!(s.pool.convert_to_assets(&s.pool.get_lender_info(&s.lender).shares), 6_000);
+
+    // New deposits buy in at the lower share price (rounded down)
+    s.env.mock_all_auths();
+    s.pool.deposit(&late_lender, &6_000);
+    assert_eq!(s.pool.get_lender_info(&late_lender).shares, 9_999);
+
+    assert_eq!(s.pool.try_write_off(&1, &1), Err(Ok(Error::InvalidAmount)));
+
+    // Selling the collateral wins part of the loss back for everyone holding shares
+    s.token_admin.mint(&s.pool.address, &3_000);
+    s.pool.recover(&3_000, &1);
+    assert_eq!(s.pool.total_recovered(), 3_000);
+    assert_eq!(s.pool.total_assets(), 15_000);
+    assert_eq!(s.pool.convert_to_assets(&s.pool.get_lender_info(&s.lender).shares), 7_500);
+    assert_eq!(s.pool.try_recover(&0, &1), Err(Ok(Error::InvalidAmount)));
+}
+
+#[test]
+fn test_written_off_shares_cannot_dilute_new_deposits() {
+    let s = setup();
+    let late_lender = Address::generate(&s.env);
+    s.token_admin.mint(&late_lender, &10_000);
+    s.env.mock_all_auths();
+
+    s.pool.borrow(&9_000, &s.borrower, &1);
+    s.pool.withdraw(&s.lender, &1_000);
+    s.pool.write_off(&9_000, &1);
+
+    // Every remaining share is worthless, so a deposit would only subsidize them
+    assert_eq!(s.pool.total_assets(), 0);
+    assert_eq!(s.pool.total_shares(), 9_000);
+    assert_eq!(s.pool.try_deposit(&late_lender, &5_000), Err(Ok(Error::PoolInsolvent)));
+
+    // Once a recovery gives the shares value again, new deposits buy in at that price
+    s.token_admin.mint(&s.pool.address, &3_000);
+    s.pool.recover(&3_000, &1);
+    s.pool.deposit(&late_lender, &3_000);
+
+    let late_shares = s.pool.get_lender_info(&late_lender).shares;
+    assert_eq!(late_shares, 8_998);
+    assert_eq!(s.pool.convert_to_assets(&late_shares), 2_999);
+    assert_eq!(s.pool.convert_to_assets(&s.pool.get_lender_info(&s.lender).shares), 3_000);
+}
+
+#[test]
+fn test_pause_deposits_and_borrows() {
+    let s = setup();
+    let pauser = Address::generate(&s.env);
+    s.pool.borrow(&4_000, &s.borrower, &1);
+
+    assert_eq!(s.pool.try_pause(&pauser, &PauseScope::Deposits), Err(Ok(Error::Unauthorized)));
+
+    s.pool.grant_role(&Role::Pauser, &pauser);
+    s.pool.pause(&pauser, &PauseScope::Deposits);
+    s.pool.pause(&pauser, &PauseScope::Borrows);
+    assert!(s.pool.is_paused(&PauseScope::Deposits));
+    assert!(s.pool.is_paused(&PauseScope::Borrows));
+
+    s.token_admin.mint(&s.lender, &1_000);
+    assert_eq!(s.pool.try_deposit(&s.lender, &1_000), Err(Ok(Error::Paused)));
+    assert_eq!(s.pool.try_borrow(&1_000, &s.borrower, &2), Err(Ok(Error::Paused)));
+
+    // Repayments and withdrawals of idle liquidity are never paused
+    s.token.transfer(&s.borrower, &s.pool.address, &2_000);
+    s.pool.repay(&2_000, &0, &1);
+    s.pool.withdraw(&s.lender, &8_000);
+    assert_eq!(s.token.balance(&s.lender), 9_000);
+
+    s.pool.unpause(&pauser, &PauseScope::Deposits);
+    assert!(!s.pool.is_paused(&PauseScope::Deposits));
+    s.pool.deposit(&s.lender, &1_000);
+    assert_eq!(s.pool.try_borrow(&1_000, &s.borrower, &2), Err(Ok(Error::Paused)));
+}
@@ -0,0 +1,119 @@
+use soroban_sdk::{ contracttype, Env, Vec };
+
+// Fixed-point scale for monthly rates and discount factors
+const SCALE: i128 = 1_000_000_000_000_000_000;
+
+// Basis points per year, spread over twelve months
+const MONTHLY_BPS: i128 = 12 * 10000;
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Installment {
+    pub number: u32, // 1-based position in the schedule
+    pub payment: i128,
+    pub principal: i128,
+    pub interest: i128,
+    pub remaining_balance: i128, // outstanding principal after this installment
+}
+
+// Monthly rate for an APR in basis points, scaled by SCALE
+fn monthly_rate(annual_rate_bps: u32) -> i128 {
+    ((annual_rate_bps as i128) * SCALE) / MONTHLY_BPS
+}
+
+// Interest accrued on `balance` over one month, rounded down
+pub fn monthly_interest(balance: i128, annual_rate_bps: u32) -> i128 {
+    (balance * monthly_rate(annual_rate_bps)) / SCALE
+}
+
+// Level annuity payment P·r / (1 - (1 + r)^-n), rounded up so the schedule never
+// leaves principal behind. The discount factor shrinks each month, so it cannot overflow.
+pub fn level_payment(principal: i128, annual_rate_bps: u32, months: u32) -> i128 {
+    let months = months as i128;
+    let rate = monthly_rate(annual_rate_bps);
+    if rate == 0 {
+        return (principal + months - 1) / months;
+    }
+
+    let mut discount = SCALE;
+    for _ in 0..months {
+        discount = (discount * SCALE) / (SCALE + rate);
+    }
+
+    let numerator = principal * rate;
+    let denominator = SCALE - discount;
+    (numerator + denominator - 1) / denominator
+}
+
+// Interest accrued on `balance` over `elapsed` seconds of a `period`-second month, rounded down
+pub fn accrued_interest(balance: i128, annual_rate_bps: u32, elapsed: u64, period: u64) -> i128 {
+    (monthly_interest(balance, annual_rate_bps) * elapsed as i128) / period as i128
+}
+
+// Full repayment schedule. Every installment but the last pays the level payment;
+// the last one clears whatever principal rounding left over.
+pub fn schedule(env: &Env, principal: i128, annual_rate_bps: u32, months: u32) -> Vec<Installment> {
+    reamortized_schedule(env, principal, annual_rate_bps, months, 1)
+}
+
+// Schedule that keeps paying `payment` until `principal` is gone, numbered from `first_number`.
+// Used after a prepayment to shorten the term instead of lowering the payment.
+pub fn shortened_schedule(
+    env: &Env,
+    principal: i128,
+    annual_rate_bps: u32,
+    payment: i128,
+    max_months: u32,
+    first_number: u32
+) -> Vec<Installment> {
+    installments(env, principal, annual_rate_bps, payment, max_months, first_number)
+}
+
+// Level-payment schedule over `months`, numbered from `first_number`.
+// Used after a prepayment to lower the payment instead of shortening the term.
+pub fn reamortized_schedule(
+    env: &Env,
+    principal: i128,
+    annual_rate_bps: u32,
+    months: u32,
+    first_number: u32
+) -> Vec<Installment> {
+    let payment = level_payment(principal, annual_rate_bps, months);
+    installments(env, principal, annual_rate_bps, payment, months, first_number)
+}
+
+// Pay `payment` a month for at most `max_months`, stopping early once the balance is cleared
+fn installments(
+    env: &Env,
+    principal: i128,
+    annual_rate_bps: u32,
+    payment: i128,
+    max_months: u32,
+    first_number: u32
+) -> Vec<Installment> {
+    let mut installments = Vec::new(env);
+    let mut balance = principal;
+    for month in 1..=max_months {
+        let interest = monthly_interest(balance, annual_rate_bps);
+        let principal_part = if month == max_months {
+            balance
+        } else {
+            (payment - interest).clamp(0, balance)
+        };
+        balance -= principal_part;
+
+        installments.push_back(Installment {
+            number: first_number + month - 1,
+            payment: principal_part + interest,
+            principal: principal_part,
+            interest,
+            remaining_balance: balance,
+        });
+
+        if balance == 0 {
+            break;
+        }
+    }
+
+    installments
+}
@@ -1,6 +1,58 @@
 #![no_std]
 
-use soroban_sdk::{ contract, contractimpl, contracttype, Address, Env, Vec, token, contractevent };
+use soroban_sdk::{
+    contract,
+    contractclient,
+    contracterror,
+    contractevent,
+    contractimpl,
+    contracttype,
+    token,
+    Address,
+    Env,
+    String,
+    BytesN,
+    TryFromVal,
+    Val,
+    Vec,
+};
+
+mod amortization;
+
+use amortization::Installment;
+use common::access::{ self, AccessError };
+use common::storage;
+
+// Longest loan term, which also bounds the stored repayment schedule
+const MAX_DURATION_MONTHS: u32 = 360;
+
+// Installments fall due every 30 days
+const MONTH_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+// Consecutive missed installments that put a loan into default
+const MISSED_PAYMENTS_TO_DEFAULT: u32 = 2;
+
+// Restructures allowed over the life of one loan, which also bounds its stored history
+const MAX_RESTRUCTURES: u32 = 3;
+
+// Longest payment holiday a restructure can grant
+const MAX_DEFERRAL_MONTHS: u32 = 6;
+
+// Highest risk premium a tier may add on top of the pool rate, in basis points
+const MAX_RISK_PREMIUM: u32 = 100_000;
+
+// Most loans a paginated view returns, and most ids `get_loans` accepts
+const MAX_PAGE_SIZE: u32 = 50;
+
+// Most loan ids `list_loans_by_status` inspects in one call, matching or not
+const MAX_STATUS_SCAN: u32 = 100;
+
+// Storage layout version written by `initialize` and brought up to date by `migrate`
+const SCHEMA_VERSION: u32 = 1;
+
+// Loans `migrate` converts per call. Instance reads and writes cost more while the original
+// layout still fills instance storage, so batches stay small.
+const MIGRATION_BATCH: u64 = 20;
 
 mod nft {
     soroban_sdk::contractimport!(
@@ -14,19 +66,41 @@ mod pool {
     );
 }
 
-// use remittance_nft::{RemittanceNFTClient};
+// OracleVerifier imports this contract's WASM, so its client is declared here rather than imported
+#[contractclient(name = "OracleClient")]
+pub trait OracleVerifierInterface {
+    fn start_monitoring_loan(env: Env, loan_id: u64);
+}
+
+// The auction house imports this contract's WASM, so its client is declared here as well
+#[contractclient(name = "AuctionClient")]
+pub trait AuctionHouseInterface {
+    fn start_auction(env: Env, loan_id: u64, nft_id: u64, valuation_months: u32);
+}
 
 #[contracttype]
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum LoanStatus {
     Pending = 0,
     Active = 1,
     Repaid = 2,
     Defaulted = 3,
+    Late = 4, // past due beyond the grace period, still repayable
+    Cancelled = 5, // withdrawn by the borrower before approval
+    Rejected = 6, // declined by the admin
+    Expired = 7, // not approved within the pending loan TTL
+    Refinanced = 8, // closed into a new loan against the same NFT
 }
 
 #[contracttype]
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum PrepaymentMode {
+    ShortenTerm = 0, // keep the monthly payment and finish sooner
+    Reamortize = 1, // keep the end date and lower the monthly payment
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Loan {
     pub loan_id: u64,
     pub borrower: Address,
@@ -40,67 +114,380 @@ pub struct Loan {
     pub start_timestamp: u64,
     pub next_payment_due: u64,
     pub status: LoanStatus,
+    pub payments_made: u32, // installments paid in full
+    pub payments_missed: u32,
+    pub installment_paid: i128, // amount paid so far toward the current installment
+    pub late_fees: i128, // late fees charged and not yet paid
+    pub late_fee_installments: u32, // installments that have been charged a late fee
+    pub prepayment_mode: PrepaymentMode,
+    pub expiry_ledger: u32, // last ledger a pending request can be approved in
+    pub restructures: Vec<Restructure>, // oldest first
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Restructure {
+    pub timestamp: u64,
+    pub outstanding_balance: i128, // principal carried into the new schedule
+    pub payments_missed: u32, // missed count cleared by the restructure
+    pub previous_rate: u32,
+    pub previous_duration: u32,
+    pub previous_payment: i128,
+    pub new_rate: u32,
+    pub new_duration: u32, // installments left after the restructure
+    pub deferral_months: u32,
+}
+
+// Loan as the original contract stored it, before schema version 1; `migrate` converts it
+#[contracttype(export = false)]
+#[derive(Clone)]
+struct LegacyLoan {
+    pub loan_id: u64,
+    pub borrower: Address,
+    pub nft_collateral_id: u64,
+    pub loan_amount: i128,
+    pub outstanding_balance: i128,
+    pub total_repaid: i128,
+    pub interest_rate: u32,
+    pub duration_months: u32,
+    pub monthly_payment: i128,
+    pub start_timestamp: u64,
+    pub next_payment_due: u64,
+    pub status: LoanStatus,
     pub payments_made: u32,
     pub payments_missed: u32,
 }
 
 #[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LateFeePolicy {
+    pub grace_period: u64, // seconds after the due date before a payment counts as late
+    pub flat_fee: i128,
+    pub fee_bps: u32, // charged on the late installment's payment amount
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RiskTier {
+    pub min_score: u32, // lowest reliability score in this tier
+    pub premium: u32, // APR added on top of the pool rate, in basis points
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AmountLimit {
+    pub min_score: u32, // lowest reliability score in this tier
+    pub max_amount: i128, // largest loan auto-approved for the tier
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnderwritingPolicy {
+    pub enabled: bool,
+    pub min_score: u32,
+    pub min_history_months: u32,
+    pub max_ltv: u32, // basis points of collateral value, applied on top of the global limit
+    pub max_missed_payments: u32, // lifetime missed remittances on the NFT
+    pub max_restructures: u32, // earlier loans against the NFT that were restructured
+    pub amount_limits: Vec<AmountLimit>, // sorted by descending min_score
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RestructurePolicy {
+    pub enabled: bool,
+    pub min_payments_made: u32, // installments the borrower must have paid first
+    pub max_deferral_months: u32, // at most MAX_DEFERRAL_MONTHS
+    pub max_extension_months: u32, // installments that may be added to the remaining term
+    pub max_rate_cut: u32, // largest APR reduction, in basis points
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateQuote {
+    pub interest_rate: u32, // APR in basis points (pool_rate + risk_premium)
+    pub pool_rate: u32,
+    pub risk_premium: u32,
+    pub monthly_payment: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoanPage {
+    pub loans: Vec<Loan>,
+    pub next_cursor: Option<u64>, // pass back to continue; None once every loan id was scanned
+}
+
+// Loan operations that can be paused on their own. Payments, payoffs and
+// prepayments are never paused.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PauseScope {
+    /// New loan requests and refinancing
+    Requests,
+    /// Funding loans, whether approved by the admin or by the underwriting policy
+    Approvals,
+}
+
+// Duties the admin can hand out without giving away the admin key.
+// The admin itself may act in every role.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    /// Pauses and resumes loan requests and approvals
+    Pauser,
+    /// Tunes LTV limits, risk tiers, fees and underwriting and restructure policies
+    RiskManager,
+}
+
+#[contracttype]
+#[derive(Clone)]
 pub enum DataKey {
     LoanCounter,
     Loan(u64),
-    BorrowerLoans(Address),
+    BorrowerLoanCount(Address),
+    BorrowerLoan(Address, u32), // (borrower, index) -> loan_id, in request order
+    BorrowerLoans(Address), // Vec<u64> of loan ids kept before schema version 1
     RemittanceNFTContract,
     LendingPoolContract,
     OracleContract,
     USDCTokenAddress,
-    AdminAddress,
+    Paused(PauseScope), // present while the scope is paused
+    RiskTiers,
+    MaxLtv,
+    Schedule(u64),
+    AuctionHouse,
+    LateFeePolicy,
+    PendingLoanTtl,
+    NftLoan(u64), // nft_id -> loan_id of the open loan it backs
+    UnderwritingPolicy,
+    RestructurePolicy,
+    SchemaVersion,
+    MigrationCursor, // last loan id an unfinished migration step has moved
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    /// The contract has already been initialized
+    AlreadyInitialized = 1,
+    /// The contract has not been initialized yet
+    NotInitialized = 2,
+    /// No loan exists with the given loan id
+    LoanNotFound = 3,
+    /// The loan is not awaiting approval
+    LoanNotPending = 4,
+    /// The loan is not active
+    LoanNotActive = 5,
+    /// The amount must be positive
+    InvalidAmount = 6,
+    /// The collateral NFT is not owned by the borrower
+    NotNftOwner = 7,
+    /// The loan duration must be between 1 and 360 months
+    InvalidDuration = 8,
+    /// Risk tiers must be sorted by descending score and end with a zero-score tier
+    InvalidRiskTiers = 9,
+    /// The pool cannot fund the loan without exceeding its utilization cap
+    InsufficientPoolLiquidity = 10,
+    /// The loan amount exceeds the allowed share of the NFT's collateral value
+    LoanExceedsCollateral = 11,
+    /// The loan-to-value limit must be between 1 and 10000 basis points
+    InvalidLtv = 12,
+    /// The loan has not defaulted
+    LoanNotDefaulted = 13,
+    /// No auction house has been configured
+    AuctionHouseNotSet = 14,
+    /// Late fees must be non-negative and at most 10000 basis points
+    InvalidLateFeePolicy = 15,
+    /// A prepayment must exceed what is currently due and leave some principal outstanding
+    InvalidPrepayment = 16,
+    /// The loan request expired before it was approved
+    LoanExpired = 17,
+    /// The pending loan TTL must be at least one ledger
+    InvalidPendingLoanTtl = 18,
+    /// The loan request is still within its approval window
+    LoanNotExpired = 19,
+    /// The NFT already backs a pending or active loan
+    NftAlreadyPledged = 20,
+    /// Policy limits must be sorted by descending score and the LTV at most 10000 basis points
+    InvalidUnderwritingPolicy = 21,
+    /// Restructured terms must not raise the rate and may defer at most six months
+    InvalidRestructure = 22,
+    /// The loan has already been restructured the maximum number of times
+    RestructureLimitReached = 23,
+    /// Refinancing would not lower the loan's interest rate
+    RefinanceNotCheaper = 24,
+    /// More loan ids were requested than one call may return
+    PageTooLarge = 25,
+    /// The caller is not the admin and does not hold the required role
+    Unauthorized = 26,
+    /// No admin transfer has been proposed
+    NoPendingAdmin = 27,
+    /// The operation is paused
+    Paused = 28,
+    /// Policy deferral must not exceed six months
+    InvalidRestructurePolicy = 29,
+    /// The restructure falls outside the policy a risk manager may apply
+    RestructureNotEligible = 30,
+}
+
+impl From<AccessError> for Error {
+    fn from(error: AccessError) -> Self {
+        match error {
+            AccessError::Unauthorized => Error::Unauthorized,
+            AccessError::NoPendingAdmin => Error::NoPendingAdmin,
+        }
+    }
 }
 
-#[contractevent]
+#[contractevent(topics = ["role_granted"], data_format = "single-value")]
+pub struct RoleGrantedEvent {
+    #[topic]
+    pub role: Role,
+    pub account: Address,
+}
+
+#[contractevent(topics = ["role_revoked"], data_format = "single-value")]
+pub struct RoleRevokedEvent {
+    #[topic]
+    pub role: Role,
+    pub account: Address,
+}
+
+#[contractevent(topics = ["loan_requested"], data_format = "single-value")]
 pub struct LoanRequestedEvent {
+    #[topic]
     pub borrower: Address,
     pub loan_id: u64,
 }
 
-#[contractevent]
+#[contractevent(topics = ["loan_approved"], data_format = "single-value")]
 pub struct LoanApprovedEvent {
     pub loan_id: u64,
 }
 
-#[contractevent]
+#[contractevent(topics = ["payment_made"], data_format = "single-value")]
 pub struct PaymentMadeEvent {
+    #[topic]
     pub loan_id: u64,
     pub amount: i128,
 }
 
-#[contractevent]
+#[contractevent(topics = ["payment_missed"], data_format = "single-value")]
 pub struct PaymentMissedEvent {
+    #[topic]
     pub loan_id: u64,
     pub missed_count: u32,
 }
 
+#[contractevent(topics = ["loan_defaulted"])]
+pub struct LoanDefaultedEvent {
+    #[topic]
+    pub loan_id: u64,
+    #[topic]
+    pub borrower: Address,
+    pub nft_id: u64,
+    pub principal_written_off: i128,
+    pub total_repaid: i128,
+}
+
+#[contractevent(topics = ["late_fee"])]
+pub struct LateFeeChargedEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub installment: u32,
+    pub fee: i128,
+}
+
+#[contractevent(topics = ["loan_late"], data_format = "single-value")]
+pub struct LoanLateEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub overdue_installments: u32,
+}
+
+#[contractevent(topics = ["loan_paid_off"], data_format = "single-value")]
+pub struct LoanPaidOffEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub amount: i128,
+}
+
+#[contractevent(topics = ["prepayment"])]
+pub struct PrepaymentEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub principal: i128,
+    pub remaining_installments: u32,
+    pub monthly_payment: i128,
+}
+
+#[contractevent(topics = ["loan_restructured"])]
+pub struct LoanRestructuredEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub interest_rate: u32,
+    pub remaining_installments: u32,
+    pub deferral_months: u32,
+    pub monthly_payment: i128,
+    pub forgone_interest: i128, // interest the lenders give up over the deferral
+}
+
+#[contractevent(topics = ["loan_refinanced"])]
+pub struct LoanRefinancedEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub new_loan_id: u64,
+    pub principal: i128,
+    pub interest_paid: i128,
+    pub interest_rate: u32,
+}
+
+#[contractevent(topics = ["loan_cancelled"], data_format = "single-value")]
+pub struct LoanCancelledEvent {
+    #[topic]
+    pub borrower: Address,
+    pub loan_id: u64,
+}
+
+#[contractevent(topics = ["loan_rejected"], data_format = "single-value")]
+pub struct LoanRejectedEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub reason: String,
+}
+
+#[contractevent(topics = ["loan_expired"], data_format = "single-value")]
+pub struct LoanExpiredEvent {
+    pub loan_id: u64,
+}
+
+#[contractevent(topics = ["paused"], data_format = "single-value")]
+pub struct PausedEvent {
+    pub scope: PauseScope,
+}
+
+#[contractevent(topics = ["unpaused"], data_format = "single-value")]
+pub struct UnpausedEvent {
+    pub scope: PauseScope,
+}
+
+#[contractevent(topics = ["upgraded"], data_format = "single-value")]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+}
+
+#[contractevent(topics = ["migrated"], data_format = "single-value")]
+pub struct MigratedEvent {
+    pub schema_version: u32,
+}
+
 #[contract]
 pub struct LoanManager;
 
 #[contractimpl]
 impl LoanManager {
-    pub fn __initialize(
-        env: Env,
-        nft_contract: Address,
-        pool_contract: Address,
-        oracle_contract: Address,
-        usdc_token: Address
-    ) {
-        env.storage().instance().set(&DataKey::RemittanceNFTContract, &nft_contract);
-        env.storage().instance().set(&DataKey::LendingPoolContract, &pool_contract);
-        env.storage().instance().set(&DataKey::OracleContract, &oracle_contract);
-        env.storage().instance().set(&DataKey::USDCTokenAddress, &usdc_token);
-        env.storage().instance().set(&DataKey::LoanCounter, &0u64);
-        env.storage()
-            .instance()
-            .set(&DataKey::AdminAddress, &env.current_contract_address());
-    }
-
     // Public initialize function that can be called after deployment
     pub fn initialize(
         env: Env,
@@ -109,22 +496,152 @@ impl LoanManager {
         pool_contract: Address,
         oracle_contract: Address,
         usdc_token: Address
-    ) {
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
         admin.require_auth();
 
         // Only allow initialization if not already initialized
-        let existing: Option<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::RemittanceNFTContract);
-        assert!(existing.is_none(), "Contract already initialized");
+        if env.storage().instance().has(&DataKey::RemittanceNFTContract) {
+            return Err(Error::AlreadyInitialized);
+        }
 
         env.storage().instance().set(&DataKey::RemittanceNFTContract, &nft_contract);
         env.storage().instance().set(&DataKey::LendingPoolContract, &pool_contract);
         env.storage().instance().set(&DataKey::OracleContract, &oracle_contract);
         env.storage().instance().set(&DataKey::USDCTokenAddress, &usdc_token);
-        env.storage().instance().set(&DataKey::AdminAddress, &admin);
+        access::set_admin(&env, &admin);
         env.storage().instance().set(&DataKey::LoanCounter, &0u64);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+
+        Ok(())
+    }
+
+    // Admin replaces the contract's code. Data is left as is; call `migrate` afterwards
+    // if the new code expects a newer storage layout.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        UpgradedEvent { wasm_hash: new_wasm_hash }.publish(&env);
+
+        Ok(())
+    }
+
+    // Storage layout version the contract's data is in; 0 for deployments that predate versioning
+    pub fn schema_version(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::SchemaVersion).unwrap_or(0)
+    }
+
+    // Admin brings stored data up to SCHEMA_VERSION. Large steps take several calls;
+    // returns the version the data is in afterwards.
+    pub fn migrate(env: Env) -> Result<u32, Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        let mut version = Self::schema_version(env.clone());
+
+        // 0 -> 1: per-loan entries move from instance to persistent storage
+        if version == 0 {
+            if !Self::migrate_to_v1(&env) {
+                return Ok(version);
+            }
+            version = 1;
+            env.storage().instance().set(&DataKey::SchemaVersion, &version);
+            MigratedEvent { schema_version: version }.publish(&env);
+        }
+
+        Ok(version)
+    }
+
+    pub fn get_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::admin(&env)
+    }
+
+    // Successor named by `propose_admin` who has not accepted yet
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::pending_admin(&env)
+    }
+
+    // Admin nominates a successor, who takes over by calling `accept_admin`
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::propose_admin(&env, &new_admin)?)
+    }
+
+    // The nominated successor takes over as admin
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::accept_admin(&env)?)
+    }
+
+    // Admin gives up control for good; admin-only calls fail from then on
+    pub fn renounce_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::renounce_admin(&env)?)
+    }
+
+    // Admin lets `account` act in `role`
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::grant_role(&env, role, &account)?;
+        RoleGrantedEvent { role, account }.publish(&env);
+        Ok(())
+    }
+
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::revoke_role(&env, role, &account)?;
+        RoleRevokedEvent { role, account }.publish(&env);
+        Ok(())
+    }
+
+    // Whether `account` may act in `role`; the admin may act in every role
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        storage::extend_instance(&env);
+
+        access::has_role(&env, role, &account)
+    }
+
+    // Pauser stops `scope` until it is unpaused; repayments keep working
+    pub fn pause(env: Env, caller: Address, scope: PauseScope) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::Pauser, &caller)?;
+
+        env.storage().instance().set(&DataKey::Paused(scope), &true);
+        PausedEvent { scope }.publish(&env);
+        Ok(())
+    }
+
+    pub fn unpause(env: Env, caller: Address, scope: PauseScope) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::Pauser, &caller)?;
+
+        env.storage().instance().remove(&DataKey::Paused(scope));
+        UnpausedEvent { scope }.publish(&env);
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env, scope: PauseScope) -> bool {
+        storage::extend_instance(&env);
+
+        env.storage().instance().has(&DataKey::Paused(scope))
     }
 
     // Request loan
@@ -134,9 +651,20 @@ impl LoanManager {
         nft_id: u64,
         amount: i128,
         duration_months: u32
-    ) -> u64 {
+    ) -> Result<u64, Error> {
+        storage::extend_instance(&env);
+
         borrower.require_auth();
 
+        Self::require_not_paused(&env, PauseScope::Requests)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if duration_months == 0 || duration_months > MAX_DURATION_MONTHS {
+            return Err(Error::InvalidDuration);
+        }
+
         // Verify NFT ownership
         let _nft_contract: Address = env
             .storage()
@@ -146,20 +674,36 @@ impl LoanManager {
         let nft_client = nft::Client::new(&env, &_nft_contract);
 
         let nft_data = nft_client.get_nft_data(&nft_id);
-        assert!(nft_data.owner == borrower, "NFT does not belong to borrower");
+        if nft_data.owner != borrower {
+            return Err(Error::NotNftOwner);
+        }
+
+        if Self::get_active_loan_for_nft(env.clone(), nft_id).is_some() {
+            return Err(Error::NftAlreadyPledged);
+        }
+
+        Self::check_ltv(&env, nft_id, amount, duration_months)?;
+
+        // Fail now rather than at approval if the pool cannot fund the loan
+        let pool_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+        let pool_client = pool::Client::new(&env, &pool_contract);
+        if amount > pool_client.max_borrowable() {
+            return Err(Error::InsufficientPoolLiquidity);
+        }
 
         // Calculate loan terms
-        let interest_rate = Self::calculate_interest_rate(&env, nft_id);
-        let monthly_payment = Self::calculate_monthly_payment(
-            amount,
-            interest_rate,
-            duration_months
-        );
+        let quote = Self::quote(&env, nft_data.reliability_score, amount, duration_months);
 
         // Create loan
         let mut counter: u64 = env.storage().instance().get(&DataKey::LoanCounter).unwrap_or(0);
         counter += 1;
 
+        let schedule = amortization::schedule(&env, amount, quote.interest_rate, duration_months);
+
         let loan = Loan {
             loan_id: counter,
             borrower: borrower.clone(),
@@ -167,131 +711,1202 @@ impl LoanManager {
             loan_amount: amount,
             outstanding_balance: amount,
             total_repaid: 0,
-            interest_rate,
-            duration_months,
-            monthly_payment,
+            interest_rate: quote.interest_rate,
+            duration_months: schedule.len(),
+            monthly_payment: quote.monthly_payment,
             start_timestamp: env.ledger().timestamp(),
-            next_payment_due: env.ledger().timestamp() + 30 * 24 * 60 * 60, // 30 days
+            next_payment_due: env.ledger().timestamp() + MONTH_SECONDS,
             status: LoanStatus::Pending,
             payments_made: 0,
             payments_missed: 0,
+            installment_paid: 0,
+            late_fees: 0,
+            late_fee_installments: 0,
+            prepayment_mode: PrepaymentMode::ShortenTerm,
+            expiry_ledger: env.ledger().sequence() + Self::get_pending_loan_ttl(env.clone()),
+            restructures: Vec::new(&env),
         };
 
         env.storage().instance().set(&DataKey::LoanCounter, &counter);
-        env.storage().instance().set(&DataKey::Loan(counter), &loan);
+        storage::write(&env, &DataKey::Loan(counter), &loan);
+        storage::write(&env, &DataKey::Schedule(counter), &schedule);
+        storage::write(&env, &DataKey::NftLoan(nft_id), &counter);
 
-        // Track borrower loans
-        let mut borrower_loans: Vec<u64> = env
-            .storage()
-            .instance()
-            .get(&DataKey::BorrowerLoans(borrower.clone()))
-            .unwrap_or(Vec::new(&env));
-        borrower_loans.push_back(counter);
-        env.storage().instance().set(&DataKey::BorrowerLoans(borrower.clone()), &borrower_loans);
+        Self::track_borrower_loan(&env, &borrower, counter);
 
-        env.events().publish(("loan_requested", borrower), counter);
+        LoanRequestedEvent { borrower, loan_id: counter }.publish(&env);
 
-        counter
+        // Loans inside the underwriting policy are funded right away. When that is not possible
+        // right now (approvals or pool borrowing paused) the loan waits for manual approval.
+        if Self::can_fund_now(&env, &pool_client, amount)
+            && Self::meets_policy(&env, &nft_data, nft_id, amount, duration_months)
+        {
+            Self::fund_loan(&env, loan);
+        }
+
+        Ok(counter)
     }
 
     // Approve and fund loan
-    pub fn approve_loan(env: Env, loan_id: u64) {
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::AdminAddress)
-            .expect("Admin not configured");
-        admin.require_auth();
+    pub fn approve_loan(env: Env, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
 
-        let mut loan: Loan = env
-            .storage()
-            .instance()
-            .get(&DataKey::Loan(loan_id))
-            .expect("Loan does not exist");
+        access::require_admin(&env)?;
+        Self::require_not_paused(&env, PauseScope::Approvals)?;
 
-        assert!(loan.status == LoanStatus::Pending, "Loan not pending");
+        let loan = Self::get_loan(env.clone(), loan_id)?;
 
-        // Stake NFT as collateral
+        if loan.status == LoanStatus::Expired {
+            return Err(Error::LoanExpired);
+        }
+        if loan.status != LoanStatus::Pending {
+            return Err(Error::LoanNotPending);
+        }
+
+        // The NFT may have changed hands while the loan was pending
         let nft_contract: Address = env
             .storage()
             .instance()
             .get(&DataKey::RemittanceNFTContract)
             .unwrap();
         let nft_client = nft::Client::new(&env, &nft_contract);
-        nft_client.stake_nft(&loan.nft_collateral_id, &loan_id);
+        if nft_client.get_nft_data(&loan.nft_collateral_id).owner != loan.borrower {
+            return Err(Error::NotNftOwner);
+        }
 
-        // Borrow funds from the lending pool and disburse to borrower
-        let pool_contract: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::LendingPoolContract)
-            .unwrap();
-        let pool_client = pool::Client::new(&env, &pool_contract);
-        pool_client.borrow(&loan.loan_amount, &loan.borrower, &loan_id);
+        // The NFT's score may have dropped while the loan was pending
+        Self::check_ltv(&env, loan.nft_collateral_id, loan.loan_amount, loan.duration_months)?;
 
-        // Update loan status and payment schedule
-        loan.status = LoanStatus::Active;
-        loan.start_timestamp = env.ledger().timestamp();
-        loan.next_payment_due = env.ledger().timestamp() + 30 * 24 * 60 * 60;
+        Self::fund_loan(&env, loan);
+
+        Ok(())
+    }
 
-        env.storage().instance().set(&DataKey::Loan(loan_id), &loan);
+    // Admin sets the policy under which `request_loan` funds loans without manual approval
+    pub fn set_underwriting_policy(env: Env, caller: Address, policy: UnderwritingPolicy) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::RiskManager, &caller)?;
+
+        if policy.max_ltv > 10000 {
+            return Err(Error::InvalidUnderwritingPolicy);
+        }
+        for i in 1..policy.amount_limits.len() {
+            let previous = policy.amount_limits.get(i - 1).unwrap();
+            if policy.amount_limits.get(i).unwrap().min_score >= previous.min_score {
+                return Err(Error::InvalidUnderwritingPolicy);
+            }
+        }
 
-        env.events().publish(("loan_approved",), loan_id);
+        env.storage().instance().set(&DataKey::UnderwritingPolicy, &policy);
+        Ok(())
     }
 
-    // Process payment
-    pub fn make_payment(env: Env, loan_id: u64, amount: i128) {
-        let mut loan: Loan = env
-            .storage()
-            .instance()
-            .get(&DataKey::Loan(loan_id))
-            .expect("Loan does not exist");
+    pub fn get_underwriting_policy(env: Env) -> Option<UnderwritingPolicy> {
+        storage::extend_instance(&env);
 
-        assert!(loan.status == LoanStatus::Active, "Loan not active");
-        assert!(amount > 0, "Amount must be positive");
+        env.storage().instance().get(&DataKey::UnderwritingPolicy)
+    }
 
-        loan.borrower.require_auth();
+    // Whether a request for `amount` over `duration_months` against the NFT would be auto-approved
+    pub fn qualifies_for_auto_approval(
+        env: Env,
+        nft_id: u64,
+        amount: i128,
+        duration_months: u32
+    ) -> Result<bool, Error> {
+        storage::extend_instance(&env);
 
-        // Transfer USDC from borrower to pool
-        let usdc_token: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
-        let pool_contract: Address = env
+        let nft_contract: Address = env
             .storage()
             .instance()
-            .get(&DataKey::LendingPoolContract)
-            .unwrap();
-
+            .get(&DataKey::RemittanceNFTContract)
+            .ok_or(Error::NotInitialized)?;
+        let nft_client = nft::Client::new(&env, &nft_contract);
+        let nft_data = nft_client.get_nft_data(&nft_id);
+
+        Ok(Self::meets_policy(&env, &nft_data, nft_id, amount, duration_months))
+    }
+
+    // Borrower withdraws a loan request before it is approved
+    pub fn cancel_loan(env: Env, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if loan.status != LoanStatus::Pending {
+            return Err(Error::LoanNotPending);
+        }
+
+        loan.borrower.require_auth();
+
+        loan.status = LoanStatus::Cancelled;
+        Self::release_nft(&env, &loan);
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+
+        LoanCancelledEvent { borrower: loan.borrower, loan_id }.publish(&env);
+
+        Ok(())
+    }
+
+    // Admin declines a loan request
+    pub fn reject_loan(env: Env, loan_id: u64, reason: String) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if loan.status != LoanStatus::Pending {
+            return Err(Error::LoanNotPending);
+        }
+
+        loan.status = LoanStatus::Rejected;
+        Self::release_nft(&env, &loan);
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+
+        LoanRejectedEvent { loan_id, reason }.publish(&env);
+
+        Ok(())
+    }
+
+    // Record that a pending request ran out of time. `get_loan` already reports such
+    // requests as Expired; this persists the status and emits the event. Anyone can call it.
+    pub fn expire_loan(env: Env, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let stored: Loan = storage::read(&env, &DataKey::Loan(loan_id))
+            .ok_or(Error::LoanNotFound)?;
+        if stored.status != LoanStatus::Pending {
+            return Err(Error::LoanNotPending);
+        }
+
+        let loan = Self::get_loan(env.clone(), loan_id)?;
+        if loan.status != LoanStatus::Expired {
+            return Err(Error::LoanNotExpired);
+        }
+
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+        Self::release_nft(&env, &loan);
+
+        LoanExpiredEvent { loan_id }.publish(&env);
+
+        Ok(())
+    }
+
+    // Admin sets how many ledgers a loan request stays open for approval
+    pub fn set_pending_loan_ttl(env: Env, ledgers: u32) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        if ledgers == 0 {
+            return Err(Error::InvalidPendingLoanTtl);
+        }
+
+        env.storage().instance().set(&DataKey::PendingLoanTtl, &ledgers);
+        Ok(())
+    }
+
+    pub fn get_pending_loan_ttl(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
+        env.storage()
+            .instance()
+            .get(&DataKey::PendingLoanTtl)
+            .unwrap_or(120_960) // about a week of 5 second ledgers
+    }
+
+    // Process payment
+    pub fn make_payment(env: Env, loan_id: u64, amount: i128) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if !Self::is_repaying(&loan) {
+            return Err(Error::LoanNotActive);
+        }
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        loan.borrower.require_auth();
+
+        // A payment landing after the grace period pays the late fee first
+        Self::charge_late_fees(&env, &mut loan);
+
+        // Never take more than the rest of the schedule
+        let amount = amount.min(Self::remaining_due(&env, &loan));
+
+        // Transfer USDC from borrower to pool
+        let usdc_token: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
+        let pool_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+
         let usdc_client = token::Client::new(&env, &usdc_token);
         usdc_client.transfer(&loan.borrower, &pool_contract, &amount);
 
-        // Calculate principal and interest split
-        let interest_portion = Self::calculate_interest_portion(
-            loan.outstanding_balance,
-            loan.interest_rate
-        );
-        let principal_portion = if amount > interest_portion {
-            amount - interest_portion
-        } else {
-            0
+        Self::apply_payment(&env, loan, amount);
+
+        Ok(())
+    }
+
+    // Process automatic repayment (called by Oracle)
+    pub fn process_automatic_repayment(
+        env: Env,
+        loan_id: u64,
+        remittance_amount: i128
+    ) -> Result<i128, Error> {
+        storage::extend_instance(&env);
+
+        Self::require_oracle(&env)?;
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if !Self::is_repaying(&loan) {
+            return Err(Error::LoanNotActive);
+        }
+        if remittance_amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Nothing is collected during a restructure's payment holiday
+        if env.ledger().timestamp() < loan.next_payment_due.saturating_sub(MONTH_SECONDS) {
+            return Ok(remittance_amount);
+        }
+
+        Self::charge_late_fees(&env, &mut loan);
+
+        // Cover at most the late fees and the rest of the current installment
+        let payment_amount = remittance_amount.min(loan.late_fees + Self::installment_due(&env, &loan));
+
+        // Pull the installment from the borrower's pre-approved USDC allowance
+        let usdc_token: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
+        let pool_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+
+        let usdc_client = token::Client::new(&env, &usdc_token);
+        usdc_client.transfer_from(
+            &env.current_contract_address(),
+            &loan.borrower,
+            &pool_contract,
+            &payment_amount
+        );
+
+        Self::apply_payment(&env, loan, payment_amount);
+
+        // Return remaining amount for recipient
+        Ok(remittance_amount - payment_amount)
+    }
+
+    // Amount `payoff` would charge right now: outstanding principal, interest accrued
+    // since the current installment began and any late fees
+    pub fn payoff_quote(env: Env, loan_id: u64) -> Result<i128, Error> {
+        storage::extend_instance(&env);
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if !Self::is_repaying(&loan) {
+            return Err(Error::LoanNotActive);
+        }
+
+        Self::accrue_late_fees(&env, &mut loan);
+        let (principal, interest) = Self::payoff_amounts(&env, &loan);
+        Ok(principal + interest)
+    }
+
+    // Borrower repays the whole loan early and gets the NFT back
+    pub fn payoff(env: Env, loan_id: u64) -> Result<i128, Error> {
+        storage::extend_instance(&env);
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if !Self::is_repaying(&loan) {
+            return Err(Error::LoanNotActive);
+        }
+
+        loan.borrower.require_auth();
+
+        Self::charge_late_fees(&env, &mut loan);
+        let (principal, interest) = Self::payoff_amounts(&env, &loan);
+        let amount = principal + interest;
+
+        let usdc_token: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
+        let pool_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+
+        let usdc_client = token::Client::new(&env, &usdc_token);
+        usdc_client.transfer(&loan.borrower, &pool_contract, &amount);
+
+        let pool_client = pool::Client::new(&env, &pool_contract);
+        pool_client.repay(&principal, &interest, &loan_id);
+
+        loan.total_repaid += amount;
+        loan.outstanding_balance = 0;
+        loan.installment_paid = 0;
+        loan.late_fees = 0;
+        loan.status = LoanStatus::Repaid;
+        Self::release_nft(&env, &loan);
+
+        let nft_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::RemittanceNFTContract)
+            .unwrap();
+        let nft_client = nft::Client::new(&env, &nft_contract);
+        nft_client.unstake_nft(&loan.nft_collateral_id);
+
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+
+        LoanPaidOffEvent { loan_id, amount }.publish(&env);
+
+        Ok(amount)
+    }
+
+    // Borrower pays down principal early. The payment first settles late fees and the
+    // current installment; the rest reduces principal and the remaining schedule is rebuilt
+    // per the loan's prepayment mode.
+    pub fn prepay(env: Env, loan_id: u64, amount: i128) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if !Self::is_repaying(&loan) {
+            return Err(Error::LoanNotActive);
+        }
+
+        loan.borrower.require_auth();
+
+        Self::charge_late_fees(&env, &mut loan);
+        let due_now = loan.late_fees + Self::installment_due(&env, &loan);
+        if amount <= due_now {
+            return Err(Error::InvalidPrepayment);
+        }
+
+        let usdc_token: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
+        let pool_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+
+        let usdc_client = token::Client::new(&env, &usdc_token);
+        usdc_client.transfer(&loan.borrower, &pool_contract, &amount);
+
+        // Bring the loan current
+        Self::apply_payment(&env, loan, due_now);
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        let principal = amount - due_now;
+        if principal >= loan.outstanding_balance {
+            return Err(Error::InvalidPrepayment);
+        }
+
+        let pool_client = pool::Client::new(&env, &pool_contract);
+        pool_client.repay(&principal, &0, &loan_id);
+
+        loan.total_repaid += principal;
+        loan.outstanding_balance -= principal;
+
+        // Rebuild the installments that are still unpaid
+        let schedule = Self::get_schedule(env.clone(), loan_id)?;
+        let remaining = schedule.len() - loan.payments_made;
+        let rebuilt = match loan.prepayment_mode {
+            PrepaymentMode::ShortenTerm => amortization::shortened_schedule(
+                &env,
+                loan.outstanding_balance,
+                loan.interest_rate,
+                loan.monthly_payment,
+                remaining,
+                loan.payments_made + 1
+            ),
+            PrepaymentMode::Reamortize => amortization::reamortized_schedule(
+                &env,
+                loan.outstanding_balance,
+                loan.interest_rate,
+                remaining,
+                loan.payments_made + 1
+            ),
+        };
+
+        let mut new_schedule = schedule.slice(0..loan.payments_made);
+        new_schedule.append(&rebuilt);
+
+        loan.duration_months = new_schedule.len();
+        loan.monthly_payment = rebuilt.get(0).unwrap().payment;
+
+        storage::write(&env, &DataKey::Schedule(loan_id), &new_schedule);
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+
+        PrepaymentEvent {
+            loan_id,
+            principal,
+            remaining_installments: rebuilt.len(),
+            monthly_payment: loan.monthly_payment,
+        }.publish(&env);
+
+        Ok(())
+    }
+
+    // Borrower whose score has improved moves an active loan to a new one priced at their
+    // current tier. The new loan's principal repays the old one's inside the pool, so only
+    // accrued interest and late fees change hands; the NFT stays staked throughout.
+    // Like a new request, the new loan is approved by the underwriting policy or, outside
+    // it, by the admin co-signing the call.
+    pub fn refinance(env: Env, loan_id: u64, new_duration: u32) -> Result<u64, Error> {
+        storage::extend_instance(&env);
+
+        Self::require_not_paused(&env, PauseScope::Requests)?;
+        Self::require_not_paused(&env, PauseScope::Approvals)?;
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if loan.status != LoanStatus::Active {
+            return Err(Error::LoanNotActive);
+        }
+        if new_duration == 0 || new_duration > MAX_DURATION_MONTHS {
+            return Err(Error::InvalidDuration);
+        }
+
+        loan.borrower.require_auth();
+
+        let nft_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::RemittanceNFTContract)
+            .unwrap();
+        let nft_client = nft::Client::new(&env, &nft_contract);
+        let nft_data = nft_client.get_nft_data(&loan.nft_collateral_id);
+
+        Self::charge_late_fees(&env, &mut loan);
+        let (principal, interest) = Self::payoff_amounts(&env, &loan);
+
+        Self::check_ltv(&env, loan.nft_collateral_id, principal, new_duration)?;
+
+        // The principal is already lent out, so price it at today's utilization
+        let quote = Self::quote(&env, nft_data.reliability_score, 0, new_duration);
+        if quote.interest_rate >= loan.interest_rate {
+            return Err(Error::RefinanceNotCheaper);
+        }
+        if !Self::meets_policy(&env, &nft_data, loan.nft_collateral_id, principal, new_duration) {
+            access::require_admin(&env)?;
+        }
+
+        let usdc_token: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
+        let pool_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+
+        let usdc_client = token::Client::new(&env, &usdc_token);
+        usdc_client.transfer(&loan.borrower, &pool_contract, &interest);
+
+        // Principal rolls over, leaving TotalBorrowed unchanged
+        let pool_client = pool::Client::new(&env, &pool_contract);
+        pool_client.repay(&0, &interest, &loan_id);
+
+        let mut counter: u64 = env.storage().instance().get(&DataKey::LoanCounter).unwrap_or(0);
+        counter += 1;
+
+        let schedule = amortization::schedule(&env, principal, quote.interest_rate, new_duration);
+        let now = env.ledger().timestamp();
+        let new_loan = Loan {
+            loan_id: counter,
+            loan_amount: principal,
+            outstanding_balance: principal,
+            total_repaid: 0,
+            interest_rate: quote.interest_rate,
+            duration_months: schedule.len(),
+            monthly_payment: amortization::level_payment(principal, quote.interest_rate, new_duration),
+            start_timestamp: now,
+            next_payment_due: now + MONTH_SECONDS,
+            status: LoanStatus::Active,
+            payments_made: 0,
+            payments_missed: 0,
+            installment_paid: 0,
+            late_fees: 0,
+            late_fee_installments: 0,
+            restructures: Vec::new(&env),
+            ..loan.clone()
+        };
+
+        loan.total_repaid += principal + interest;
+        loan.outstanding_balance = 0;
+        loan.installment_paid = 0;
+        loan.late_fees = 0;
+        loan.status = LoanStatus::Refinanced;
+
+        env.storage().instance().set(&DataKey::LoanCounter, &counter);
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+        storage::write(&env, &DataKey::Loan(counter), &new_loan);
+        storage::write(&env, &DataKey::Schedule(counter), &schedule);
+        storage::write(&env, &DataKey::NftLoan(loan.nft_collateral_id), &counter);
+
+        Self::track_borrower_loan(&env, &loan.borrower, counter);
+
+        nft_client.restake_nft(&loan.nft_collateral_id, &counter);
+
+        let oracle_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::OracleContract)
+            .unwrap();
+        OracleClient::new(&env, &oracle_contract).start_monitoring_loan(&counter);
+
+        LoanRefinancedEvent {
+            loan_id,
+            new_loan_id: counter,
+            principal,
+            interest_paid: interest,
+            interest_rate: quote.interest_rate,
+        }.publish(&env);
+
+        Ok(counter)
+    }
+
+    // Borrower chooses whether prepayments shorten the term or lower the payment
+    pub fn set_prepayment_mode(env: Env, loan_id: u64, mode: PrepaymentMode) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+        loan.borrower.require_auth();
+
+        loan.prepayment_mode = mode;
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+
+        Ok(())
+    }
+
+    // Admin sets the limits within which risk managers may restructure loans
+    pub fn set_restructure_policy(env: Env, caller: Address, policy: RestructurePolicy) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::RiskManager, &caller)?;
+
+        if policy.max_deferral_months > MAX_DEFERRAL_MONTHS {
+            return Err(Error::InvalidRestructurePolicy);
+        }
+
+        env.storage().instance().set(&DataKey::RestructurePolicy, &policy);
+        Ok(())
+    }
+
+    pub fn get_restructure_policy(env: Env) -> Option<RestructurePolicy> {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::RestructurePolicy)
+    }
+
+    // Admin or risk manager grants forbearance to a struggling borrower: the outstanding
+    // principal is re-amortized over `new_duration` installments at `new_rate`, with the
+    // first one due after `deferral_months` interest-free months. Unpaid late fees stay
+    // owed, the missed-payment count and Late status are cleared, and the NFT is tagged.
+    // Risk managers are held to the restructure policy; the admin is not.
+    //
+    // No interest accrues during the deferral and the rebuilt schedule does not recover
+    // it, so lenders absorb that interest; the event reports the amount given up.
+    pub fn restructure_loan(
+        env: Env,
+        caller: Address,
+        loan_id: u64,
+        new_duration: u32,
+        new_rate: u32,
+        deferral_months: u32
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::RiskManager, &caller)?;
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if !Self::is_repaying(&loan) {
+            return Err(Error::LoanNotActive);
+        }
+        if loan.restructures.len() >= MAX_RESTRUCTURES {
+            return Err(Error::RestructureLimitReached);
+        }
+        if new_duration == 0 || loan.payments_made + new_duration > MAX_DURATION_MONTHS {
+            return Err(Error::InvalidDuration);
+        }
+        if new_rate > loan.interest_rate || deferral_months > MAX_DEFERRAL_MONTHS {
+            return Err(Error::InvalidRestructure);
+        }
+        if access::admin(&env) != Some(caller)
+            && !Self::within_restructure_policy(&env, &loan, new_duration, new_rate, deferral_months)
+        {
+            return Err(Error::RestructureNotEligible);
+        }
+
+        // Fees for installments already overdue are kept before the schedule changes
+        Self::charge_late_fees(&env, &mut loan);
+
+        // Whatever was paid toward the current installment has already reached the pool
+        let schedule = Self::get_schedule(env.clone(), loan_id)?;
+        let rebuilt = amortization::reamortized_schedule(
+            &env,
+            loan.outstanding_balance,
+            new_rate,
+            new_duration,
+            loan.payments_made + 1
+        );
+        let mut new_schedule = schedule.slice(0..loan.payments_made);
+        new_schedule.append(&rebuilt);
+        let forgone_interest =
+            amortization::monthly_interest(loan.outstanding_balance, new_rate) * deferral_months as i128;
+
+        loan.restructures.push_back(Restructure {
+            timestamp: env.ledger().timestamp(),
+            outstanding_balance: loan.outstanding_balance,
+            payments_missed: loan.payments_missed,
+            previous_rate: loan.interest_rate,
+            previous_duration: loan.duration_months,
+            previous_payment: loan.monthly_payment,
+            new_rate,
+            new_duration,
+            deferral_months,
+        });
+
+        loan.interest_rate = new_rate;
+        loan.duration_months = new_schedule.len();
+        loan.monthly_payment = rebuilt.get(0).unwrap().payment;
+        loan.installment_paid = 0;
+        loan.next_payment_due = env.ledger().timestamp() + (deferral_months as u64 + 1) * MONTH_SECONDS;
+        loan.late_fee_installments = loan.payments_made;
+        loan.payments_missed = 0;
+        loan.status = LoanStatus::Active;
+
+        storage::write(&env, &DataKey::Schedule(loan_id), &new_schedule);
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+
+        // Later underwriting sees that this NFT needed forbearance
+        let nft_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::RemittanceNFTContract)
+            .unwrap();
+        let nft_client = nft::Client::new(&env, &nft_contract);
+        nft_client.record_restructure(&loan.nft_collateral_id, &loan_id);
+
+        LoanRestructuredEvent {
+            loan_id,
+            interest_rate: new_rate,
+            remaining_installments: rebuilt.len(),
+            deferral_months,
+            monthly_payment: loan.monthly_payment,
+            forgone_interest,
+        }.publish(&env);
+
+        Ok(())
+    }
+
+    // Mark payment as missed (called by Oracle)
+    pub fn mark_payment_missed(env: Env, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_oracle(&env)?;
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if !Self::is_repaying(&loan) {
+            return Err(Error::LoanNotActive);
+        }
+
+        loan.payments_missed += 1;
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+
+        PaymentMissedEvent { loan_id, missed_count: loan.payments_missed }.publish(&env);
+
+        // Check for default (consecutive missed payments)
+        if loan.payments_missed >= MISSED_PAYMENTS_TO_DEFAULT {
+            Self::default_loan(&env, loan);
+        }
+
+        Ok(())
+    }
+
+    // Anyone can flag an overdue loan from ledger time alone, without waiting on the oracle.
+    // Charges late fees and marks the loan Late. It never defaults a loan: that stays with
+    // the oracle's missed-payment reports, whose count it leaves untouched.
+    pub fn check_delinquency(env: Env, loan_id: u64) -> Result<LoanStatus, Error> {
+        storage::extend_instance(&env);
+
+        let mut loan = Self::get_loan(env.clone(), loan_id)?;
+
+        if !Self::is_repaying(&loan) {
+            return Err(Error::LoanNotActive);
+        }
+
+        let overdue = Self::overdue_installments(&env, &loan);
+        if overdue == 0 {
+            return Ok(loan.status);
+        }
+
+        Self::charge_late_fees(&env, &mut loan);
+        loan.status = LoanStatus::Late;
+        storage::write(&env, &DataKey::Loan(loan_id), &loan);
+
+        LoanLateEvent { loan_id, overdue_installments: overdue }.publish(&env);
+
+        Ok(LoanStatus::Late)
+    }
+
+    // Admin sets the grace period and late fee schedule
+    pub fn set_late_fee_policy(env: Env, caller: Address, policy: LateFeePolicy) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::RiskManager, &caller)?;
+
+        if policy.flat_fee < 0 || policy.fee_bps > 10000 {
+            return Err(Error::InvalidLateFeePolicy);
+        }
+
+        env.storage().instance().set(&DataKey::LateFeePolicy, &policy);
+        Ok(())
+    }
+
+    pub fn get_late_fee_policy(env: Env) -> LateFeePolicy {
+        storage::extend_instance(&env);
+
+        env.storage()
+            .instance()
+            .get(&DataKey::LateFeePolicy)
+            .unwrap_or(LateFeePolicy {
+                grace_period: 3 * 24 * 60 * 60, // 3 days
+                flat_fee: 0,
+                fee_bps: 500,
+            })
+    }
+
+    // Preview the APR and monthly payment a borrower would get for a loan request
+    pub fn quote_rate(
+        env: Env,
+        nft_id: u64,
+        amount: i128,
+        duration_months: u32
+    ) -> Result<RateQuote, Error> {
+        storage::extend_instance(&env);
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if duration_months == 0 || duration_months > MAX_DURATION_MONTHS {
+            return Err(Error::InvalidDuration);
+        }
+
+        let nft_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::RemittanceNFTContract)
+            .ok_or(Error::NotInitialized)?;
+        let nft_client = nft::Client::new(&env, &nft_contract);
+        let nft_data = nft_client.get_nft_data(&nft_id);
+
+        Ok(Self::quote(&env, nft_data.reliability_score, amount, duration_months))
+    }
+
+    // Admin replaces the score-based risk premium tiers
+    pub fn set_risk_tiers(env: Env, caller: Address, tiers: Vec<RiskTier>) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::RiskManager, &caller)?;
+
+        if tiers.is_empty() || tiers.last().unwrap().min_score != 0 {
+            return Err(Error::InvalidRiskTiers);
+        }
+        if tiers.iter().any(|tier| tier.premium > MAX_RISK_PREMIUM) {
+            return Err(Error::InvalidRiskTiers);
+        }
+        for i in 1..tiers.len() {
+            if tiers.get(i).unwrap().min_score >= tiers.get(i - 1).unwrap().min_score {
+                return Err(Error::InvalidRiskTiers);
+            }
+        }
+
+        env.storage().instance().set(&DataKey::RiskTiers, &tiers);
+        Ok(())
+    }
+
+    pub fn get_risk_tiers(env: Env) -> Vec<RiskTier> {
+        storage::extend_instance(&env);
+
+        env.storage()
+            .instance()
+            .get(&DataKey::RiskTiers)
+            .unwrap_or_else(|| {
+                Vec::from_array(
+                    &env,
+                    [
+                        RiskTier { min_score: 90, premium: 0 },
+                        RiskTier { min_score: 80, premium: 500 },
+                        RiskTier { min_score: 70, premium: 1500 },
+                        RiskTier { min_score: 0, premium: 2500 },
+                    ]
+                )
+            })
+    }
+
+    // Repayment schedule fixed when the loan was requested
+    pub fn get_schedule(env: Env, loan_id: u64) -> Result<Vec<Installment>, Error> {
+        storage::extend_instance(&env);
+
+        storage::read(&env, &DataKey::Schedule(loan_id))
+            .ok_or(Error::LoanNotFound)
+    }
+
+    // Admin sets the contract that auctions seized collateral
+    pub fn set_auction_house(env: Env, auction_house: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        env.storage().instance().set(&DataKey::AuctionHouse, &auction_house);
+        Ok(())
+    }
+
+    pub fn get_auction_house(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::AuctionHouse)
+    }
+
+    // Book collateral auction proceeds already paid into the pool (called by the auction house only)
+    pub fn recover_defaulted(env: Env, loan_id: u64, amount: i128) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let auction_house: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::AuctionHouse)
+            .ok_or(Error::AuctionHouseNotSet)?;
+        auction_house.require_auth();
+
+        let loan = Self::get_loan(env.clone(), loan_id)?;
+        if loan.status != LoanStatus::Defaulted {
+            return Err(Error::LoanNotDefaulted);
+        }
+
+        let pool_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+        let pool_client = pool::Client::new(&env, &pool_contract);
+        pool_client.recover(&amount, &loan_id);
+
+        Ok(())
+    }
+
+    // Admin sets the maximum loan-to-value ratio in basis points of collateral value
+    pub fn set_max_ltv(env: Env, caller: Address, max_ltv: u32) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::RiskManager, &caller)?;
+
+        if max_ltv == 0 || max_ltv > 10000 {
+            return Err(Error::InvalidLtv);
+        }
+
+        env.storage().instance().set(&DataKey::MaxLtv, &max_ltv);
+        Ok(())
+    }
+
+    pub fn get_max_ltv(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::MaxLtv).unwrap_or(10000)
+    }
+
+    // Number of loans requested so far; loan ids run from 1 to this
+    pub fn get_loan_counter(env: Env) -> u64 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::LoanCounter).unwrap_or(0)
+    }
+
+    // Number of loans the borrower has requested, including refinanced ones
+    pub fn get_borrower_loan_count(env: Env, borrower: Address) -> u32 {
+        storage::extend_instance(&env);
+
+        storage::read(&env, &DataKey::BorrowerLoanCount(borrower))
+            .unwrap_or(0)
+    }
+
+    // Borrower's loans in request order, starting at `offset`; at most MAX_PAGE_SIZE per call.
+    // Entries no longer in storage are skipped, so a page can come back short.
+    pub fn get_borrower_loans(env: Env, borrower: Address, offset: u32, limit: u32) -> Vec<Loan> {
+        storage::extend_instance(&env);
+
+        let count = Self::get_borrower_loan_count(env.clone(), borrower.clone());
+        let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(count);
+
+        let mut loans = Vec::new(&env);
+        for index in offset..end {
+            let loan_id: Option<u64> = storage::read(&env, &DataKey::BorrowerLoan(borrower.clone(), index));
+            if let Some(Ok(loan)) = loan_id.map(|loan_id| Self::get_loan(env.clone(), loan_id)) {
+                loans.push_back(loan);
+            }
+        }
+        loans
+    }
+
+    // Loans in `status` with ids after `cursor` (0 to start), in id order. Each call returns at
+    // most `limit` loans (capped at MAX_PAGE_SIZE) and inspects at most MAX_STATUS_SCAN ids, so
+    // a page can come back short or empty while `next_cursor` still points further on. Ids
+    // whose loan is no longer in storage are skipped.
+    pub fn list_loans_by_status(env: Env, status: LoanStatus, cursor: u64, limit: u32) -> LoanPage {
+        storage::extend_instance(&env);
+
+        let counter: u64 = env.storage().instance().get(&DataKey::LoanCounter).unwrap_or(0);
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let scan_end = counter.min(cursor.saturating_add(MAX_STATUS_SCAN as u64));
+
+        let mut loans = Vec::new(&env);
+        let mut loan_id = cursor;
+        while loan_id < scan_end && loans.len() < limit {
+            loan_id += 1;
+            if let Ok(loan) = Self::get_loan(env.clone(), loan_id) {
+                if loan.status == status {
+                    loans.push_back(loan);
+                }
+            }
+        }
+
+        LoanPage {
+            loans,
+            next_cursor: if loan_id < counter { Some(loan_id) } else { None },
+        }
+    }
+
+    // Fetch several loans at once, in the order given
+    pub fn get_loans(env: Env, ids: Vec<u64>) -> Result<Vec<Loan>, Error> {
+        storage::extend_instance(&env);
+
+        if ids.len() > MAX_PAGE_SIZE {
+            return Err(Error::PageTooLarge);
+        }
+
+        let mut loans = Vec::new(&env);
+        for loan_id in ids.iter() {
+            loans.push_back(Self::get_loan(env.clone(), loan_id)?);
+        }
+        Ok(loans)
+    }
+
+    // The pending or active loan an NFT backs, if any
+    pub fn get_active_loan_for_nft(env: Env, nft_id: u64) -> Option<Loan> {
+        storage::extend_instance(&env);
+
+        let loan_id: u64 = storage::read(&env, &DataKey::NftLoan(nft_id))?;
+        let loan = Self::get_loan(env, loan_id).ok()?;
+
+        // An expired request no longer holds the NFT even before `expire_loan` records it
+        match loan.status {
+            LoanStatus::Pending | LoanStatus::Active | LoanStatus::Late => Some(loan),
+            _ => None,
+        }
+    }
+
+    // Get loan details
+    pub fn get_loan(env: Env, loan_id: u64) -> Result<Loan, Error> {
+        storage::extend_instance(&env);
+
+        let mut loan: Loan = storage::read(&env, &DataKey::Loan(loan_id))
+            .ok_or(Error::LoanNotFound)?;
+
+        // Requests left pending past their expiry ledger lapse on their own
+        if loan.status == LoanStatus::Pending && env.ledger().sequence() > loan.expiry_ledger {
+            loan.status = LoanStatus::Expired;
+        }
+
+        Ok(loan)
+    }
+
+    // Anyone can extend the TTL of persistent entries, e.g. a keeper keeping live data
+    // from being archived. Returns how many of `keys` exist.
+    pub fn bump(env: Env, keys: Vec<DataKey>) -> u32 {
+        storage::extend_instance(&env);
+
+        let mut extended = 0;
+        for key in keys.iter() {
+            if storage::extend(&env, &key) {
+                extended += 1;
+            }
+        }
+        extended
+    }
+
+    // Internal: Fail while `scope` is paused
+    fn require_not_paused(env: &Env, scope: PauseScope) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Paused(scope)) {
+            return Err(Error::Paused);
+        }
+        Ok(())
+    }
+
+    // Internal: Move the next MIGRATION_BATCH loans the original contract kept in instance
+    // storage, converting each along with its borrower's index. Returns true once every
+    // loan is moved.
+    fn migrate_to_v1(env: &Env) -> bool {
+        let counter: u64 = env.storage().instance().get(&DataKey::LoanCounter).unwrap_or(0);
+        let cursor: u64 = env.storage().instance().get(&DataKey::MigrationCursor).unwrap_or(0);
+        let end = counter.min(cursor + MIGRATION_BATCH);
+
+        for loan_id in (cursor + 1)..=end {
+            if !storage::migrate(env, &DataKey::Loan(loan_id)) {
+                continue;
+            }
+
+            // The original layout kept each borrower's loans in one vector, in id order, so
+            // indexing loans as they move rebuilds it a batch at a time
+            if let Some(loan) = Self::read_migrated_loan(env, loan_id) {
+                Self::track_borrower_loan(env, &loan.borrower, loan_id);
+                env.storage().instance().remove(&DataKey::BorrowerLoans(loan.borrower));
+            }
+        }
+
+        if end < counter {
+            env.storage().instance().set(&DataKey::MigrationCursor, &end);
+            return false;
+        }
+        env.storage().instance().remove(&DataKey::MigrationCursor);
+        true
+    }
+
+    // Internal: A moved loan in the current layout. One stored by the original contract gets
+    // the fields added since, a schedule that re-amortizes its outstanding principal over the
+    // remaining term at its rate, and, while open, the lock on its NFT.
+    fn read_migrated_loan(env: &Env, loan_id: u64) -> Option<Loan> {
+        let value: Val = storage::read(env, &DataKey::Loan(loan_id))?;
+        if storage::has_field(env, &value, "restructures") {
+            return Loan::try_from_val(env, &value).ok();
+        }
+        let legacy = LegacyLoan::try_from_val(env, &value).ok()?;
+
+        // Installments already paid keep their place in the schedule
+        let mut schedule = Vec::new(env);
+        if legacy.payments_made > 0 {
+            let term = legacy.duration_months.max(legacy.payments_made);
+            schedule = amortization::schedule(env, legacy.loan_amount, legacy.interest_rate, term);
+            schedule = schedule.slice(0..legacy.payments_made.min(schedule.len()));
+        }
+        let outstanding_balance = legacy.outstanding_balance.max(0);
+        if outstanding_balance > 0 {
+            schedule.append(&amortization::reamortized_schedule(
+                env,
+                outstanding_balance,
+                legacy.interest_rate,
+                legacy.duration_months.saturating_sub(legacy.payments_made).max(1),
+                legacy.payments_made + 1
+            ));
+        }
+
+        let loan = Loan {
+            loan_id,
+            borrower: legacy.borrower,
+            nft_collateral_id: legacy.nft_collateral_id,
+            loan_amount: legacy.loan_amount,
+            outstanding_balance,
+            total_repaid: legacy.total_repaid,
+            interest_rate: legacy.interest_rate,
+            duration_months: schedule.len(),
+            monthly_payment: schedule
+                .get(legacy.payments_made)
+                .map_or(legacy.monthly_payment, |installment| installment.payment),
+            start_timestamp: legacy.start_timestamp,
+            next_payment_due: legacy.next_payment_due,
+            status: legacy.status,
+            payments_made: legacy.payments_made,
+            payments_missed: legacy.payments_missed,
+            installment_paid: 0,
+            late_fees: 0,
+            late_fee_installments: legacy.payments_made,
+            prepayment_mode: PrepaymentMode::ShortenTerm,
+            expiry_ledger: env.ledger().sequence() + Self::get_pending_loan_ttl(env.clone()),
+            restructures: Vec::new(env),
         };
 
+        storage::write(env, &DataKey::Loan(loan_id), &loan);
+        storage::write(env, &DataKey::Schedule(loan_id), &schedule);
+        if loan.status == LoanStatus::Pending || loan.status == LoanStatus::Active {
+            storage::write(env, &DataKey::NftLoan(loan.nft_collateral_id), &loan_id);
+        }
+        Some(loan)
+    }
+
+    // Internal: Require auth from the configured Oracle contract
+    fn require_oracle(env: &Env) -> Result<(), Error> {
+        let oracle: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::OracleContract)
+            .ok_or(Error::NotInitialized)?;
+        oracle.require_auth();
+        Ok(())
+    }
+
+    // Internal: Apply a payment that has already been transferred to the pool
+    fn apply_payment(env: &Env, mut loan: Loan, amount: i128) {
+        let schedule = Self::get_schedule(env.clone(), loan.loan_id).unwrap();
+
+        // Late fees come first and go to lenders like interest
+        let fees_paid = amount.min(loan.late_fees);
+        loan.late_fees -= fees_paid;
+
+        // Walk the schedule, paying each installment's interest before its principal
+        let mut interest_portion = fees_paid;
+        let mut principal_portion = 0i128;
+        let mut left = amount - fees_paid;
+        while left > 0 && loan.payments_made < schedule.len() {
+            let installment = schedule.get(loan.payments_made).unwrap();
+            let paid = left.min(installment.payment - loan.installment_paid);
+
+            let interest_left = (installment.interest - loan.installment_paid).max(0);
+            let interest = paid.min(interest_left);
+            interest_portion += interest;
+            principal_portion += paid - interest;
+
+            loan.installment_paid += paid;
+            left -= paid;
+
+            if loan.installment_paid == installment.payment {
+                loan.installment_paid = 0;
+                loan.payments_made += 1;
+                loan.next_payment_due += MONTH_SECONDS;
+                loan.payments_missed = 0;
+            }
+        }
+
+        // Catching up on every overdue installment clears the late flag
+        if loan.status == LoanStatus::Late && Self::overdue_installments(env, &loan) == 0 {
+            loan.status = LoanStatus::Active;
+        }
+
         // Update loan
         loan.total_repaid += amount;
         loan.outstanding_balance -= principal_portion;
-        loan.payments_made += 1;
-        loan.next_payment_due += 30 * 24 * 60 * 60; // Next month
 
         // Check if fully repaid
-        if loan.outstanding_balance <= 0 {
+        if loan.payments_made == schedule.len() && loan.late_fees == 0 {
             loan.status = LoanStatus::Repaid;
+            Self::release_nft(env, &loan);
 
             // Unstake NFT
-            let _nft_contract: Address = env
+            let nft_contract: Address = env
                 .storage()
                 .instance()
                 .get(&DataKey::RemittanceNFTContract)
                 .unwrap();
-            // Call _nft_contract.unstake_nft(loan.nft_collateral_id)
-            let nft_client = nft::Client::new(&env, &_nft_contract);
+            let nft_client = nft::Client::new(env, &nft_contract);
             nft_client.unstake_nft(&loan.nft_collateral_id);
         }
 
@@ -301,109 +1916,329 @@ impl LoanManager {
             .instance()
             .get(&DataKey::LendingPoolContract)
             .unwrap();
-        let pool_client = pool::Client::new(&env, &pool_contract);
-        pool_client.repay(&principal_portion, &interest_portion, &loan_id);
-        // Use existing `pool_contract` variable above for notifications.
-        // Call pool_contract.repay(principal_portion, interest_portion, loan_id)
+        let pool_client = pool::Client::new(env, &pool_contract);
+        pool_client.repay(&principal_portion, &interest_portion, &loan.loan_id);
 
-        env.storage().instance().set(&DataKey::Loan(loan_id), &loan);
+        storage::write(env, &DataKey::Loan(loan.loan_id), &loan);
 
-        env.events().publish(("payment_made", loan_id), amount);
+        PaymentMadeEvent { loan_id: loan.loan_id, amount }.publish(env);
     }
 
-    // Process automatic repayment (called by Oracle)
-    pub fn process_automatic_repayment(env: Env, loan_id: u64, remittance_amount: i128) -> i128 {
-        let oracle: Address = env.storage().instance().get(&DataKey::OracleContract).unwrap();
+    // Internal: Seize the collateral and write off the unpaid principal in the pool
+    fn default_loan(env: &Env, mut loan: Loan) {
+        loan.status = LoanStatus::Defaulted;
+        Self::release_nft(env, &loan);
+
+        // The protocol takes the NFT so it can be sold to recover the loss
+        let nft_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::RemittanceNFTContract)
+            .unwrap();
+        let nft_client = nft::Client::new(env, &nft_contract);
+        let auction_house: Option<Address> = env.storage().instance().get(&DataKey::AuctionHouse);
+        match auction_house {
+            Some(auction_house) => {
+                nft_client.seize_nft(&loan.nft_collateral_id, &auction_house);
+
+                // Value the NFT on the remittances the borrower still owed
+                let remaining_months = (loan.duration_months - loan.payments_made).max(1);
+                AuctionClient::new(env, &auction_house).start_auction(
+                    &loan.loan_id,
+                    &loan.nft_collateral_id,
+                    &remaining_months
+                );
+            }
+            None => nft_client.seize_nft(&loan.nft_collateral_id, &env.current_contract_address()),
+        }
 
-        let loan: Loan = env
+        let pool_contract: Address = env
             .storage()
             .instance()
-            .get(&DataKey::Loan(loan_id))
-            .expect("Loan does not exist");
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+        let pool_client = pool::Client::new(env, &pool_contract);
+        pool_client.write_off(&loan.outstanding_balance, &loan.loan_id);
+
+        storage::write(env, &DataKey::Loan(loan.loan_id), &loan);
+
+        LoanDefaultedEvent {
+            loan_id: loan.loan_id,
+            borrower: loan.borrower,
+            nft_id: loan.nft_collateral_id,
+            principal_written_off: loan.outstanding_balance,
+            total_repaid: loan.total_repaid,
+        }.publish(env);
+    }
 
-        let payment_amount = if remittance_amount >= loan.monthly_payment {
-            loan.monthly_payment
-        } else {
-            remittance_amount
-        };
+    // Internal: Stake the collateral, disburse from the pool and start oracle monitoring
+    fn fund_loan(env: &Env, mut loan: Loan) {
+        // Stake NFT as collateral
+        let nft_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::RemittanceNFTContract)
+            .unwrap();
+        let nft_client = nft::Client::new(env, &nft_contract);
+        nft_client.stake_nft(&loan.nft_collateral_id, &loan.loan_id);
 
-        // Process payment
-        Self::make_payment(env.clone(), loan_id, payment_amount);
+        // Borrow funds from the lending pool and disburse to borrower
+        let pool_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+        let pool_client = pool::Client::new(env, &pool_contract);
+        pool_client.borrow(&loan.loan_amount, &loan.borrower, &loan.loan_id);
 
-        // Return remaining amount for recipient
-        remittance_amount - payment_amount
+        // Update loan status and payment schedule
+        loan.status = LoanStatus::Active;
+        loan.start_timestamp = env.ledger().timestamp();
+        loan.next_payment_due = env.ledger().timestamp() + MONTH_SECONDS;
+
+        storage::write(env, &DataKey::Loan(loan.loan_id), &loan);
+
+        // Have the oracle watch for remittances that repay this loan
+        let oracle_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::OracleContract)
+            .unwrap();
+        let oracle_client = OracleClient::new(env, &oracle_contract);
+        oracle_client.start_monitoring_loan(&loan.loan_id);
+
+        LoanApprovedEvent { loan_id: loan.loan_id }.publish(env);
     }
 
-    // Mark payment as missed (called by Oracle)
-    pub fn mark_payment_missed(env: Env, loan_id: u64) {
-        let oracle: Address = env.storage().instance().get(&DataKey::OracleContract).unwrap();
-        oracle.require_auth();
+    // Internal: Whether the restructure policy lets a risk manager apply these terms
+    fn within_restructure_policy(
+        env: &Env,
+        loan: &Loan,
+        new_duration: u32,
+        new_rate: u32,
+        deferral_months: u32
+    ) -> bool {
+        let policy: Option<RestructurePolicy> = env.storage().instance().get(&DataKey::RestructurePolicy);
+        let Some(policy) = policy else {
+            return false;
+        };
+        let remaining = loan.duration_months - loan.payments_made;
+
+        policy.enabled
+            && loan.payments_made >= policy.min_payments_made
+            && deferral_months <= policy.max_deferral_months
+            && new_duration <= remaining + policy.max_extension_months
+            && loan.interest_rate - new_rate <= policy.max_rate_cut
+    }
+
+    // Internal: Whether the underwriting policy covers a loan against this NFT
+    fn meets_policy(
+        env: &Env,
+        nft_data: &nft::RemittanceData,
+        nft_id: u64,
+        amount: i128,
+        duration_months: u32
+    ) -> bool {
+        let policy = match Self::get_underwriting_policy(env.clone()) {
+            Some(policy) if policy.enabled => policy,
+            _ => return false,
+        };
+
+        // Self-reported NFT data is left for manual approval
+        if !nft_data.verified
+            || nft_data.reliability_score < policy.min_score
+            || nft_data.history_months < policy.min_history_months
+            || nft_data.lifetime_missed_payments > policy.max_missed_payments
+            || nft_data.restructure_count > policy.max_restructures
+        {
+            return false;
+        }
 
-        let mut loan: Loan = env
+        let tier_limit = policy.amount_limits
+            .iter()
+            .find(|limit| nft_data.reliability_score >= limit.min_score);
+        match tier_limit {
+            Some(limit) if amount <= limit.max_amount => (),
+            _ => return false,
+        }
+
+        let nft_contract: Address = env
             .storage()
             .instance()
-            .get(&DataKey::Loan(loan_id))
-            .expect("Loan does not exist");
+            .get(&DataKey::RemittanceNFTContract)
+            .unwrap();
+        let nft_client = nft::Client::new(env, &nft_contract);
+        let collateral_value = nft_client.calculate_collateral_value(&nft_id, &duration_months);
+        amount * 10000 <= collateral_value * (policy.max_ltv as i128)
+    }
 
-        loan.payments_missed += 1;
+    // Internal: Append a loan to the borrower's history, one storage entry per loan
+    fn track_borrower_loan(env: &Env, borrower: &Address, loan_id: u64) {
+        let count = Self::get_borrower_loan_count(env.clone(), borrower.clone());
+        storage::write(env, &DataKey::BorrowerLoan(borrower.clone(), count), &loan_id);
+        storage::write(env, &DataKey::BorrowerLoanCount(borrower.clone()), &(count + 1));
+    }
 
-        // Check for default (2 consecutive missed payments)
-        if loan.payments_missed >= 2 {
-            loan.status = LoanStatus::Defaulted;
-            // Liquidation logic would go here
+    // Internal: Free the NFT to back a new loan once `loan` is closed
+    fn release_nft(env: &Env, loan: &Loan) {
+        let key = DataKey::NftLoan(loan.nft_collateral_id);
+        let locked: Option<u64> = storage::read(env, &key);
+        if locked == Some(loan.loan_id) {
+            storage::remove(env, &key);
         }
+    }
+
+    // Internal: Loans still being repaid, on time or not
+    fn is_repaying(loan: &Loan) -> bool {
+        loan.status == LoanStatus::Active || loan.status == LoanStatus::Late
+    }
 
-        env.storage().instance().set(&DataKey::Loan(loan_id), &loan);
+    // Internal: Unpaid installments whose due date plus grace period has passed
+    fn overdue_installments(env: &Env, loan: &Loan) -> u32 {
+        let policy = Self::get_late_fee_policy(env.clone());
+        let now = env.ledger().timestamp();
+        let late_after = loan.next_payment_due + policy.grace_period;
+        if now <= late_after {
+            return 0;
+        }
 
-        env.events().publish(("payment_missed", loan_id), loan.payments_missed);
+        let overdue = ((now - late_after - 1) / MONTH_SECONDS + 1) as u32;
+        overdue.min(loan.duration_months - loan.payments_made)
     }
 
-    // Get loan details
-    pub fn get_loan(env: Env, loan_id: u64) -> Loan {
-        env.storage().instance().get(&DataKey::Loan(loan_id)).expect("Loan does not exist")
+    // Internal: Charge one late fee for each overdue installment not charged yet
+    fn charge_late_fees(env: &Env, loan: &mut Loan) {
+        for (installment, fee) in Self::accrue_late_fees(env, loan).iter() {
+            LateFeeChargedEvent { loan_id: loan.loan_id, installment, fee }.publish(env);
+        }
     }
 
-    fn calculate_interest_rate(env: &Env, nft_id: u64) -> u32 {
-        // Keep a reference to the NFT contract for potential future calls; underscore to silence unused variable warnings.
-        let _nft_contract: Address = env
+    // Internal: Add the late fees `charge_late_fees` would charge to `loan`, returning (installment, fee) pairs
+    fn accrue_late_fees(env: &Env, loan: &mut Loan) -> Vec<(u32, i128)> {
+        let mut charged = Vec::new(env);
+
+        let overdue = Self::overdue_installments(env, loan);
+        let last_overdue = loan.payments_made + overdue;
+        if last_overdue <= loan.late_fee_installments {
+            return charged;
+        }
+
+        let policy = Self::get_late_fee_policy(env.clone());
+        let schedule = Self::get_schedule(env.clone(), loan.loan_id).unwrap();
+        let first = loan.late_fee_installments.max(loan.payments_made);
+        for index in first..last_overdue {
+            let installment = schedule.get(index).unwrap();
+            let fee = policy.flat_fee + (installment.payment * policy.fee_bps as i128) / 10000;
+            loan.late_fees += fee;
+            charged.push_back((installment.number, fee));
+        }
+        loan.late_fee_installments = last_overdue;
+
+        charged
+    }
+
+    // Internal: Principal and interest (including late fees already charged) to close the loan now
+    fn payoff_amounts(env: &Env, loan: &Loan) -> (i128, i128) {
+        let schedule = Self::get_schedule(env.clone(), loan.loan_id).unwrap();
+        let now = env.ledger().timestamp();
+        let period_start = loan.next_payment_due.saturating_sub(MONTH_SECONDS);
+
+        // Interest on the balance since the current installment's period began
+        let accrued = if now > period_start {
+            amortization::accrued_interest(
+                loan.outstanding_balance,
+                loan.interest_rate,
+                now - period_start,
+                MONTH_SECONDS
+            )
+        } else {
+            0
+        };
+
+        // Less any of that interest the borrower already paid toward the installment
+        let interest_paid = match schedule.get(loan.payments_made) {
+            Some(installment) => loan.installment_paid.min(installment.interest),
+            None => 0,
+        };
+
+        (loan.outstanding_balance, (accrued - interest_paid).max(0) + loan.late_fees)
+    }
+
+    // Internal: What is still owed on the current installment
+    fn installment_due(env: &Env, loan: &Loan) -> i128 {
+        let schedule = Self::get_schedule(env.clone(), loan.loan_id).unwrap();
+        match schedule.get(loan.payments_made) {
+            Some(installment) => installment.payment - loan.installment_paid,
+            None => 0,
+        }
+    }
+
+    // Internal: What is still owed on all unpaid installments
+    fn remaining_due(env: &Env, loan: &Loan) -> i128 {
+        let schedule = Self::get_schedule(env.clone(), loan.loan_id).unwrap();
+        let mut due = loan.late_fees - loan.installment_paid;
+        for i in loan.payments_made..schedule.len() {
+            due += schedule.get(i).unwrap().payment;
+        }
+        due
+    }
+
+    // Internal: Reject loans larger than the allowed share of the NFT's collateral value
+    fn check_ltv(env: &Env, nft_id: u64, amount: i128, duration_months: u32) -> Result<(), Error> {
+        let nft_contract: Address = env
             .storage()
             .instance()
             .get(&DataKey::RemittanceNFTContract)
             .unwrap();
-        let nft_client = nft::Client::new(&env, &_nft_contract);
-
-        // Call NFT contract to get NFT data
-        let nft_data = nft_client.get_nft_data(&nft_id);
+        let nft_client = nft::Client::new(env, &nft_contract);
 
-        // Placeholder scoring logic using nft_id
-        let score = (nft_data.reliability_score % 100) as u32;
-        if score >= 90 {
-            1500u32
-        } else if score >= 80 {
-            2000u32
-        } else if score >= 70 {
-            3000u32
-        } else {
-            4000u32
+        let collateral_value = nft_client.calculate_collateral_value(&nft_id, &duration_months);
+        let max_ltv = Self::get_max_ltv(env.clone());
+        if amount * 10000 > collateral_value * (max_ltv as i128) {
+            return Err(Error::LoanExceedsCollateral);
         }
+        Ok(())
     }
 
-    // Internal: Calculate monthly payment
-    fn calculate_monthly_payment(principal: i128, annual_rate_bps: u32, months: u32) -> i128 {
-        // Monthly rate = annual rate / 12
-        let monthly_rate_bps = annual_rate_bps / 12;
+    // Internal: Price a loan from the pool's utilization rate plus the NFT's risk premium
+    fn quote(env: &Env, reliability_score: u32, amount: i128, duration_months: u32) -> RateQuote {
+        let pool_contract: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LendingPoolContract)
+            .unwrap();
+        let pool_client = pool::Client::new(env, &pool_contract);
+
+        let pool_rate = pool_client.get_borrow_rate(&amount);
+        let risk_premium = Self::risk_premium(env, reliability_score);
+        let interest_rate = pool_rate + risk_premium;
 
-        // Simple interest calculation for MVP
-        let total_interest =
-            (principal * (annual_rate_bps as i128) * (months as i128)) / (12 * 10000);
-        let total_repayment = principal + total_interest;
+        RateQuote {
+            interest_rate,
+            pool_rate,
+            risk_premium,
+            monthly_payment: amortization::level_payment(amount, interest_rate, duration_months),
+        }
+    }
 
-        total_repayment / (months as i128)
+    // Internal: Whether `amount` could be funded in this call without the pool rejecting it
+    fn can_fund_now(env: &Env, pool_client: &pool::Client, amount: i128) -> bool {
+        !Self::is_paused(env.clone(), PauseScope::Approvals)
+            && !pool_client.is_paused(&pool::PauseScope::Borrows)
+            && amount <= pool_client.max_borrowable()
     }
 
-    // Internal: Calculate interest portion of payment
-    fn calculate_interest_portion(outstanding: i128, annual_rate_bps: u32) -> i128 {
-        let monthly_rate_bps = annual_rate_bps / 12;
-        (outstanding * (monthly_rate_bps as i128)) / 10000
+    // Internal: Premium of the first tier whose minimum score the NFT meets
+    fn risk_premium(env: &Env, reliability_score: u32) -> u32 {
+        let tiers = Self::get_risk_tiers(env.clone());
+        for tier in tiers.iter() {
+            if reliability_score >= tier.min_score {
+                return tier.premium;
+            }
+        }
+        tiers.last().map(|tier| tier.premium).unwrap_or(0)
     }
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,1630 @@
+use super::*;
+use soroban_sdk::{
+    contract,
+    contractimpl,
+    testutils::{storage::{Instance as _, Persistent as _}, Address as _, Ledger, MockAuth, MockAuthInvoke},
+    token::{StellarAssetClient, TokenClient},
+    IntoVal,
+};
+
+// This contract's own build, to upgrade a natively registered instance to
+mod loan_manager_wasm {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/loan_manager.wasm"
+    );
+}
+
+// The contracts as first deployed, built from the original sources, to upgrade from
+mod baseline {
+    pub mod loan_manager {
+        soroban_sdk::contractimport!(file = "fixtures/baseline/loan_manager.wasm");
+    }
+    pub mod nft {
+        soroban_sdk::contractimport!(file = "fixtures/baseline/remittance_nft.wasm");
+    }
+    pub mod pool {
+        soroban_sdk::contractimport!(file = "fixtures/baseline/lending_pool.wasm");
+    }
+}
+
+// Stand-in for OracleVerifier, which imports this contract's WASM
+#[contract]
+struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    pub fn start_monitoring_loan(_env: Env, _loan_id: u64) {}
+}
+
+struct Setup<'a> {
+    env: Env,
+    loan_manager: LoanManagerClient<'a>,
+    pool: pool::Client<'a>,
+    nft: nft::Client<'a>,
+    token: TokenClient<'a>,
+    admin: Address,
+    borrower: Address,
+    prime_nft: u64,
+    subprime_nft: u64,
+}
+
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let admin = Address::generate(&env);
+    let lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let token = TokenClient::new(&env, &usdc.address());
+    let token_admin = StellarAssetClient::new(&env, &usdc.address());
+    token_admin.mint(&lender, &50_000);
+    token_admin.mint(&borrower, &5_000);
+
+    let nft_id = env.register(nft::WASM, ());
+    let pool_id = env.register(pool::WASM, ());
+    let loan_manager_id = env.register(LoanManager, ());
+    let oracle_id = env.register(MockOracle, ());
+
+    let nft = nft::Client::new(&env, &nft_id);
+    let pool = pool::Client::new(&env, &pool_id);
+    let loan_manager = LoanManagerClient::new(&env, &loan_manager_id);
+
+    nft.initialize(&admin, &oracle_id, &loan_manager_id);
+    pool.initialize(&admin, &loan_manager_id, &usdc.address(), &500);
+    loan_manager.initialize(&admin, &nft_id, &pool_id, &oracle_id, &usdc.address());
+    pool.deposit(&lender, &50_000);
+
+    // Worth 79_800 and 63_000 of collateral over 12 months
+    let prime_nft = nft.mint_verified(&borrower, &10_000, &95, &12, &120_000, &Vec::new(&env));
+    let subprime_nft = nft.mint_verified(&borrower, &10_000, &75, &12, &120_000, &Vec::new(&env));
+
+    Setup { env, loan_manager, pool, nft, token, admin, borrower, prime_nft, subprime_nft }
+}
+
+#[test]
+fn test_quote_rate_adds_risk_premium_to_pool_rate() {
+    let s = setup();
+
+    // 10_000 of 50_000 is 20% utilization: 500 base + 100 from the low slope
+    let prime = s.loan_manager.quote_rate(&s.prime_nft, &10_000, &12);
+    assert_eq!(prime.pool_rate, 600);
+    assert_eq!(prime.risk_premium, 0);
+    assert_eq!(prime.interest_rate, 600);
+    assert_eq!(prime.monthly_payment, 861);
+
+    let subprime = s.loan_manager.quote_rate(&s.subprime_nft, &10_000, &12);
+    assert_eq!(subprime.pool_rate, 600);
+    assert_eq!(subprime.risk_premium, 1_500);
+    assert_eq!(subprime.interest_rate, 2_100);
+    assert_eq!(subprime.monthly_payment, 932);
+
+    // Larger requests push utilization past the kink
+    assert_eq!(s.loan_manager.quote_rate(&s.prime_nft, &45_000, &12).pool_rate, 3_900);
+}
+
+#[test]
+fn test_request_loan_uses_quote() {
+    let s = setup();
+
+    let quote = s.loan_manager.quote_rate(&s.subprime_nft, &10_000, &12);
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.interest_rate, quote.interest_rate);
+    assert_eq!(loan.monthly_payment, quote.monthly_payment);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+}
+
+#[test]
+fn test_set_risk_tiers() {
+    let s = setup();
+
+    let tiers = Vec::from_array(
+        &s.env,
+        [RiskTier { min_score: 70, premium: 200 }, RiskTier { min_score: 0, premium: 1_000 }]
+    );
+    s.loan_manager.set_risk_tiers(&s.admin, &tiers);
+    assert_eq!(s.loan_manager.get_risk_tiers(), tiers);
+    assert_eq!(s.loan_manager.quote_rate(&s.subprime_nft, &10_000, &12).risk_premium, 200);
+
+    let unsorted = Vec::from_array(
+        &s.env,
+        [RiskTier { min_score: 0, premium: 0 }, RiskTier { min_score: 70, premium: 200 }]
+    );
+    assert_eq!(s.loan_manager.try_set_risk_tiers(&s.admin, &unsorted), Err(Ok(Error::InvalidRiskTiers)));
+
+    let no_floor = Vec::from_array(&s.env, [RiskTier { min_score: 50, premium: 0 }]);
+    assert_eq!(s.loan_manager.try_set_risk_tiers(&s.admin, &no_floor), Err(Ok(Error::InvalidRiskTiers)));
+    assert_eq!(s.loan_manager.try_set_risk_tiers(&s.admin, &Vec::new(&s.env)), Err(Ok(Error::InvalidRiskTiers)));
+
+    let oversized = Vec::from_array(&s.env, [RiskTier { min_score: 0, premium: u32::MAX }]);
+    assert_eq!(s.loan_manager.try_set_risk_tiers(&s.admin, &oversized), Err(Ok(Error::InvalidRiskTiers)));
+
+    assert_eq!(s.loan_manager.try_quote_rate(&s.prime_nft, &0, &12), Err(Ok(Error::InvalidAmount)));
+    assert_eq!(s.loan_manager.try_quote_rate(&s.prime_nft, &1_000, &0), Err(Ok(Error::InvalidDuration)));
+}
+
+#[test]
+fn test_request_loan_checks_pool_capacity() {
+    let s = setup();
+
+    // 90% of the 50_000 deposited can be lent out
+    assert_eq!(s.pool.max_borrowable(), 45_000);
+    assert_eq!(
+        s.loan_manager.try_request_loan(&s.borrower, &s.prime_nft, &45_001, &12),
+        Err(Ok(Error::InsufficientPoolLiquidity))
+    );
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &45_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+    assert_eq!(s.pool.max_borrowable(), 0);
+    assert_eq!(s.pool.get_utilization_rate(), 9_000);
+}
+
+#[test]
+fn test_loan_to_value_limit() {
+    let s = setup();
+
+    assert_eq!(s.loan_manager.get_max_ltv(), 10_000);
+    assert_eq!(
+        s.loan_manager.try_request_loan(&s.borrower, &s.subprime_nft, &63_001, &12),
+        Err(Ok(Error::LoanExceedsCollateral))
+    );
+    // A shorter duration pledges fewer months of remittances
+    assert_eq!(
+        s.loan_manager.try_request_loan(&s.borrower, &s.subprime_nft, &40_000, &6),
+        Err(Ok(Error::LoanExceedsCollateral))
+    );
+
+    s.loan_manager.set_max_ltv(&s.admin, &5_000);
+    assert_eq!(
+        s.loan_manager.try_request_loan(&s.borrower, &s.prime_nft, &40_000, &12),
+        Err(Ok(Error::LoanExceedsCollateral))
+    );
+    s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &39_900, &12);
+
+    assert_eq!(s.loan_manager.try_set_max_ltv(&s.admin, &0), Err(Ok(Error::InvalidLtv)));
+    assert_eq!(s.loan_manager.try_set_max_ltv(&s.admin, &10_001), Err(Ok(Error::InvalidLtv)));
+}
+
+#[test]
+fn test_approve_rechecks_loan_to_value() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &30_000, &12);
+
+    // A missed payment while pending drops the score and the collateral value
+    s.nft.mark_payment_missed(&s.prime_nft);
+    assert_eq!(
+        s.loan_manager.try_approve_loan(&loan_id),
+        Err(Ok(Error::LoanExceedsCollateral))
+    );
+    assert!(!s.nft.get_nft_data(&s.prime_nft).is_staked);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+}
+
+#[test]
+fn test_approve_rejects_collateral_that_changed_hands() {
+    let s = setup();
+    let buyer = Address::generate(&s.env);
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+
+    // A pending loan doesn't stake the NFT, so the borrower can still move it
+    s.nft.transfer(&s.borrower, &buyer, &s.prime_nft);
+    assert_eq!(
+        s.loan_manager.try_approve_loan(&loan_id),
+        Err(Ok(Error::NotNftOwner))
+    );
+    assert!(!s.nft.get_nft_data(&s.prime_nft).is_staked);
+    assert_eq!(s.nft.get_nft_data(&s.prime_nft).owner, buyer);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+}
+
+#[test]
+fn test_amortization_schedule() {
+    let s = setup();
+
+    // 10_000 over 12 months at 21% APR
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    let schedule = s.loan_manager.get_schedule(&loan_id);
+    assert_eq!(schedule.len(), 12);
+
+    assert_eq!(
+        schedule.get(0).unwrap(),
+        Installment { number: 1, payment: 932, principal: 757, interest: 175, remaining_balance: 9_243 }
+    );
+    assert_eq!(
+        schedule.get(10).unwrap(),
+        Installment { number: 11, payment: 932, principal: 901, interest: 31, remaining_balance: 900 }
+    );
+    // The final installment absorbs the rounding
+    assert_eq!(
+        schedule.get(11).unwrap(),
+        Installment { number: 12, payment: 915, principal: 900, interest: 15, remaining_balance: 0 }
+    );
+
+    let principal: i128 = schedule.iter().map(|i| i.principal).sum();
+    assert_eq!(principal, 10_000);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).monthly_payment, 932);
+
+    assert_eq!(s.loan_manager.try_get_schedule(&99), Err(Ok(Error::LoanNotFound)));
+    assert_eq!(
+        s.loan_manager.try_request_loan(&s.borrower, &s.prime_nft, &1_000, &361),
+        Err(Ok(Error::InvalidDuration))
+    );
+}
+
+#[test]
+fn test_payments_follow_schedule() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+
+    // A partial payment goes to the first installment's interest before its principal
+    s.loan_manager.make_payment(&loan_id, &500);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.payments_made, 0);
+    assert_eq!(loan.installment_paid, 500);
+    assert_eq!(loan.outstanding_balance, 10_000 - 325);
+
+    // Completing it and paying the second in full
+    s.loan_manager.make_payment(&loan_id, &(432 + 932));
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.payments_made, 2);
+    assert_eq!(loan.installment_paid, 0);
+    assert_eq!(loan.outstanding_balance, 8_472);
+
+    // Overpaying only takes what the schedule still asks for
+    let balance_before = s.token.balance(&s.borrower);
+    s.loan_manager.make_payment(&loan_id, &20_000);
+    assert_eq!(balance_before - s.token.balance(&s.borrower), 932 * 9 + 915);
+
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.status, LoanStatus::Repaid);
+    assert_eq!(loan.outstanding_balance, 0);
+    assert_eq!(loan.total_repaid, 932 * 11 + 915);
+    assert!(!s.nft.get_nft_data(&s.subprime_nft).is_staked);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+}
+
+#[test]
+fn test_default_seizes_nft_and_writes_off_principal() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+    s.loan_manager.make_payment(&loan_id, &932);
+
+    s.loan_manager.mark_payment_missed(&loan_id);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Active);
+
+    s.loan_manager.mark_payment_missed(&loan_id);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.status, LoanStatus::Defaulted);
+    assert_eq!(loan.outstanding_balance, 9_243);
+
+    let nft_data = s.nft.get_nft_data(&s.subprime_nft);
+    assert_eq!(nft_data.owner, s.loan_manager.address);
+    assert!(!nft_data.is_staked);
+
+    // Lenders absorb the unpaid principal through the share price
+    assert_eq!(s.pool.total_losses(), 9_243);
+    assert_eq!(s.pool.total_assets(), 50_000 - 9_243);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+
+    assert_eq!(
+        s.loan_manager.try_mark_payment_missed(&loan_id),
+        Err(Ok(Error::LoanNotActive))
+    );
+    assert_eq!(
+        s.loan_manager.try_recover_defaulted(&loan_id, &1_000),
+        Err(Ok(Error::AuctionHouseNotSet))
+    );
+}
+
+#[test]
+fn test_recover_requires_defaulted_loan() {
+    let s = setup();
+    let auction_house = Address::generate(&s.env);
+    s.loan_manager.set_auction_house(&auction_house);
+    assert_eq!(s.loan_manager.get_auction_house(), Some(auction_house));
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+    assert_eq!(
+        s.loan_manager.try_recover_defaulted(&loan_id, &1_000),
+        Err(Ok(Error::LoanNotDefaulted))
+    );
+}
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn set_time(s: &Setup, timestamp: u64) {
+    s.env.ledger().with_mut(|l| l.timestamp = timestamp);
+}
+
+#[test]
+fn test_late_payment_pays_fee_first() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+
+    // Paying inside the grace period costs nothing extra
+    set_time(&s, 33 * DAY);
+    s.loan_manager.make_payment(&loan_id, &932);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).late_fees, 0);
+
+    // One second past the second installment's grace period: 5% of 932
+    set_time(&s, 63 * DAY + 1);
+    s.loan_manager.make_payment(&loan_id, &932);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.late_fees, 0);
+    assert_eq!(loan.late_fee_installments, 2);
+    assert_eq!(loan.payments_made, 1);
+    assert_eq!(loan.installment_paid, 932 - 46);
+
+    s.loan_manager.make_payment(&loan_id, &46);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.payments_made, 2);
+    assert_eq!(loan.total_repaid, 932 * 2 + 46);
+    assert_eq!(loan.outstanding_balance, 8_472);
+}
+
+#[test]
+fn test_check_delinquency() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+
+    set_time(&s, 33 * DAY);
+    assert_eq!(s.loan_manager.check_delinquency(&loan_id), LoanStatus::Active);
+
+    set_time(&s, 33 * DAY + 1);
+    assert_eq!(s.loan_manager.check_delinquency(&loan_id), LoanStatus::Late);
+    assert_eq!(s.loan_manager.check_delinquency(&loan_id), LoanStatus::Late);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.status, LoanStatus::Late);
+    assert_eq!(loan.late_fees, 46);
+    assert_eq!(loan.payments_missed, 0);
+
+    // Catching up returns the loan to good standing
+    s.loan_manager.make_payment(&loan_id, &(46 + 932));
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.status, LoanStatus::Active);
+    assert_eq!(loan.payments_missed, 0);
+
+    // Two more installments lapse with nothing paid; anyone can only mark the loan late
+    set_time(&s, 93 * DAY + 1);
+    assert_eq!(s.loan_manager.check_delinquency(&loan_id), LoanStatus::Late);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.status, LoanStatus::Late);
+    assert_eq!(loan.payments_missed, 0);
+    assert_eq!(s.pool.total_losses(), 0);
+
+    // Default still takes the oracle's missed-payment reports
+    s.loan_manager.mark_payment_missed(&loan_id);
+    s.loan_manager.mark_payment_missed(&loan_id);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Defaulted);
+    assert_eq!(s.pool.total_losses(), 9_243);
+    assert_eq!(
+        s.loan_manager.try_check_delinquency(&loan_id),
+        Err(Ok(Error::LoanNotActive))
+    );
+}
+
+#[test]
+fn test_term_follows_a_schedule_that_ends_early() {
+    let s = setup();
+
+    // At a payment of 1 a month, 10 is paid off well before 36 months
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10, &36);
+    s.loan_manager.approve_loan(&loan_id);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.duration_months, s.loan_manager.get_schedule(&loan_id).len());
+    assert_eq!(loan.duration_months, 10);
+
+    // Late fees only cover the installments the schedule has
+    set_time(&s, 400 * DAY);
+    assert_eq!(s.loan_manager.check_delinquency(&loan_id), LoanStatus::Late);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.late_fee_installments, 10);
+    s.loan_manager.make_payment(&loan_id, &(10 + loan.late_fees));
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Repaid);
+}
+
+#[test]
+fn test_late_fee_policy() {
+    let s = setup();
+
+    let policy = LateFeePolicy { grace_period: DAY, flat_fee: 10, fee_bps: 100 };
+    s.loan_manager.set_late_fee_policy(&s.admin, &policy);
+    assert_eq!(s.loan_manager.get_late_fee_policy(), policy);
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+    set_time(&s, 31 * DAY + 1);
+    s.loan_manager.check_delinquency(&loan_id);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).late_fees, 10 + 9);
+
+    assert_eq!(
+        s.loan_manager.try_set_late_fee_policy(&s.admin, &LateFeePolicy { flat_fee: -1, ..policy.clone() }),
+        Err(Ok(Error::InvalidLateFeePolicy))
+    );
+    assert_eq!(
+        s.loan_manager.try_set_late_fee_policy(&s.admin, &LateFeePolicy { fee_bps: 10_001, ..policy }),
+        Err(Ok(Error::InvalidLateFeePolicy))
+    );
+}
+
+#[test]
+fn test_payoff() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+
+    // Half a month of 175 interest on 10_000
+    set_time(&s, 15 * DAY);
+    assert_eq!(s.loan_manager.payoff_quote(&loan_id), 10_087);
+
+    let balance_before = s.token.balance(&s.borrower);
+    assert_eq!(s.loan_manager.payoff(&loan_id), 10_087);
+    assert_eq!(balance_before - s.token.balance(&s.borrower), 10_087);
+
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.status, LoanStatus::Repaid);
+    assert_eq!(loan.outstanding_balance, 0);
+    assert!(!s.nft.get_nft_data(&s.subprime_nft).is_staked);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+
+    assert_eq!(s.loan_manager.try_payoff_quote(&loan_id), Err(Ok(Error::LoanNotActive)));
+    assert_eq!(s.loan_manager.try_payoff(&loan_id), Err(Ok(Error::LoanNotActive)));
+}
+
+#[test]
+fn test_payoff_quote_after_paying_ahead() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+
+    // The next period has not started, so only principal is owed
+    set_time(&s, 10 * DAY);
+    s.loan_manager.make_payment(&loan_id, &932);
+    assert_eq!(s.loan_manager.payoff_quote(&loan_id), 9_243);
+
+    // Late fees are part of the payoff
+    set_time(&s, 63 * DAY + 1);
+    assert_eq!(s.loan_manager.payoff_quote(&loan_id), 9_243 + 161 * 33 / 30 + 46);
+}
+
+#[test]
+fn test_prepay_shortens_term() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+
+    set_time(&s, 10 * DAY);
+    assert_eq!(s.loan_manager.try_prepay(&loan_id, &932), Err(Ok(Error::InvalidPrepayment)));
+    s.loan_manager.prepay(&loan_id, &(932 + 3_000));
+
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.payments_made, 1);
+    assert_eq!(loan.outstanding_balance, 6_243);
+    assert_eq!(loan.monthly_payment, 932);
+    assert_eq!(loan.duration_months, 9);
+
+    let schedule = s.loan_manager.get_schedule(&loan_id);
+    assert_eq!(schedule.len(), 9);
+    assert_eq!(schedule.get(0).unwrap().principal, 757);
+    assert_eq!(
+        schedule.get(1).unwrap(),
+        Installment { number: 2, payment: 932, principal: 823, interest: 109, remaining_balance: 5_420 }
+    );
+    assert_eq!(
+        schedule.get(8).unwrap(),
+        Installment { number: 9, payment: 171, principal: 169, interest: 2, remaining_balance: 0 }
+    );
+
+    // Prepaying the whole balance is a payoff
+    assert_eq!(
+        s.loan_manager.try_prepay(&loan_id, &(932 + 6_243)),
+        Err(Ok(Error::InvalidPrepayment))
+    );
+}
+
+#[test]
+fn test_prepay_reamortizes() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.set_prepayment_mode(&loan_id, &PrepaymentMode::Reamortize);
+    s.loan_manager.approve_loan(&loan_id);
+
+    set_time(&s, 10 * DAY);
+    s.loan_manager.prepay(&loan_id, &(932 + 3_000));
+
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.outstanding_balance, 6_243);
+    assert_eq!(loan.monthly_payment, 629);
+    assert_eq!(loan.duration_months, 12);
+
+    let schedule = s.loan_manager.get_schedule(&loan_id);
+    assert_eq!(schedule.len(), 12);
+    assert_eq!(
+        schedule.get(11).unwrap(),
+        Installment { number: 12, payment: 622, principal: 612, interest: 10, remaining_balance: 0 }
+    );
+
+    // The new schedule is what later payments follow
+    s.loan_manager.make_payment(&loan_id, &629);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.payments_made, 2);
+    assert_eq!(loan.outstanding_balance, 5_723);
+}
+
+#[test]
+fn test_cancel_loan() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+
+    let stranger = Address::generate(&s.env);
+    s.env.mock_auths(&[MockAuth {
+        address: &stranger,
+        invoke: &MockAuthInvoke {
+            contract: &s.loan_manager.address,
+            fn_name: "cancel_loan",
+            args: (loan_id,).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.loan_manager.try_cancel_loan(&loan_id).is_err());
+
+    s.env.mock_all_auths_allowing_non_root_auth();
+    s.loan_manager.cancel_loan(&loan_id);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Cancelled);
+
+    assert_eq!(s.loan_manager.try_cancel_loan(&loan_id), Err(Ok(Error::LoanNotPending)));
+    assert_eq!(s.loan_manager.try_approve_loan(&loan_id), Err(Ok(Error::LoanNotPending)));
+}
+
+#[test]
+fn test_reject_loan() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    s.loan_manager.reject_loan(&loan_id, &String::from_str(&s.env, "income not verified"));
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Rejected);
+
+    assert_eq!(
+        s.loan_manager.try_reject_loan(&loan_id, &String::from_str(&s.env, "again")),
+        Err(Ok(Error::LoanNotPending))
+    );
+    assert_eq!(s.loan_manager.try_approve_loan(&loan_id), Err(Ok(Error::LoanNotPending)));
+}
+
+#[test]
+fn test_pending_loan_expires() {
+    let s = setup();
+
+    s.loan_manager.set_pending_loan_ttl(&100);
+    assert_eq!(s.loan_manager.try_set_pending_loan_ttl(&0), Err(Ok(Error::InvalidPendingLoanTtl)));
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    let expiry_ledger = s.loan_manager.get_loan(&loan_id).expiry_ledger;
+    assert_eq!(expiry_ledger, s.env.ledger().sequence() + 100);
+
+    s.env.ledger().with_mut(|l| l.sequence_number = expiry_ledger);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Pending);
+    assert_eq!(s.loan_manager.try_expire_loan(&loan_id), Err(Ok(Error::LoanNotExpired)));
+
+    s.env.ledger().with_mut(|l| l.sequence_number = expiry_ledger + 1);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Expired);
+    assert_eq!(s.loan_manager.try_approve_loan(&loan_id), Err(Ok(Error::LoanExpired)));
+    assert_eq!(s.loan_manager.try_cancel_loan(&loan_id), Err(Ok(Error::LoanNotPending)));
+
+    s.loan_manager.expire_loan(&loan_id);
+    assert_eq!(s.loan_manager.try_expire_loan(&loan_id), Err(Ok(Error::LoanNotPending)));
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Expired);
+}
+
+#[test]
+fn test_nft_backs_one_loan_at_a_time() {
+    let s = setup();
+
+    assert!(s.loan_manager.get_active_loan_for_nft(&s.prime_nft).is_none());
+
+    let first = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    assert_eq!(s.loan_manager.get_active_loan_for_nft(&s.prime_nft).unwrap().loan_id, first);
+    assert_eq!(
+        s.loan_manager.try_request_loan(&s.borrower, &s.prime_nft, &5_000, &6),
+        Err(Ok(Error::NftAlreadyPledged))
+    );
+
+    // Cancelling releases the NFT
+    s.loan_manager.cancel_loan(&first);
+    assert!(s.loan_manager.get_active_loan_for_nft(&s.prime_nft).is_none());
+
+    let second = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&second);
+    assert_eq!(s.loan_manager.get_active_loan_for_nft(&s.prime_nft).unwrap().loan_id, second);
+    assert_eq!(
+        s.loan_manager.try_request_loan(&s.borrower, &s.prime_nft, &5_000, &6),
+        Err(Ok(Error::NftAlreadyPledged))
+    );
+
+    // So does repaying
+    s.loan_manager.payoff(&second);
+    assert!(s.loan_manager.get_active_loan_for_nft(&s.prime_nft).is_none());
+    s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &5_000, &6);
+}
+
+#[test]
+fn test_rejected_and_expired_requests_release_nft() {
+    let s = setup();
+    s.loan_manager.set_pending_loan_ttl(&100);
+
+    let rejected = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    s.loan_manager.reject_loan(&rejected, &String::from_str(&s.env, "no"));
+    assert!(s.loan_manager.get_active_loan_for_nft(&s.prime_nft).is_none());
+
+    s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    s.env.ledger().with_mut(|l| l.sequence_number += 101);
+
+    // Expiry frees the NFT without anyone calling `expire_loan`
+    assert!(s.loan_manager.get_active_loan_for_nft(&s.prime_nft).is_none());
+    let fresh = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    assert_eq!(s.loan_manager.get_active_loan_for_nft(&s.prime_nft).unwrap().loan_id, fresh);
+}
+
+fn auto_approval_policy(env: &Env) -> UnderwritingPolicy {
+    UnderwritingPolicy {
+        enabled: true,
+        min_score: 80,
+        min_history_months: 6,
+        max_ltv: 5_000,
+        max_missed_payments: 1,
+        max_restructures: 0,
+        amount_limits: Vec::from_array(
+            env,
+            [
+                AmountLimit { min_score: 90, max_amount: 20_000 },
+                AmountLimit { min_score: 80, max_amount: 5_000 },
+            ]
+        ),
+    }
+}
+
+#[test]
+fn test_policy_auto_approves_qualifying_loans() {
+    let s = setup();
+    s.loan_manager.set_underwriting_policy(&s.admin, &auto_approval_policy(&s.env));
+
+    assert!(s.loan_manager.qualifies_for_auto_approval(&s.prime_nft, &10_000, &12));
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.status, LoanStatus::Active);
+    assert_eq!(s.token.balance(&s.borrower), 5_000 + 10_000);
+    assert!(s.nft.get_nft_data(&s.prime_nft).is_staked);
+    assert_eq!(s.pool.get_utilization_rate(), 2_000);
+}
+
+#[test]
+fn test_loans_outside_policy_wait_for_approval() {
+    let s = setup();
+    s.loan_manager.set_underwriting_policy(&s.admin, &auto_approval_policy(&s.env));
+
+    // Score below the policy minimum
+    assert!(!s.loan_manager.qualifies_for_auto_approval(&s.subprime_nft, &1_000, &12));
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &1_000, &12);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Pending);
+
+    // Above the tier's amount limit, then above the policy LTV
+    assert!(!s.loan_manager.qualifies_for_auto_approval(&s.prime_nft, &20_001, &12));
+    assert!(!s.loan_manager.qualifies_for_auto_approval(&s.prime_nft, &10_000, &2));
+
+    // Too little history
+    let new_nft = s.nft.mint_verified(&s.borrower, &10_000, &95, &3, &30_000, &Vec::new(&s.env));
+    assert!(!s.loan_manager.qualifies_for_auto_approval(&new_nft, &1_000, &12));
+
+    // Mid tier limit
+    let mid_nft = s.nft.mint_verified(&s.borrower, &10_000, &85, &12, &120_000, &Vec::new(&s.env));
+    assert!(s.loan_manager.qualifies_for_auto_approval(&mid_nft, &5_000, &12));
+    assert!(!s.loan_manager.qualifies_for_auto_approval(&mid_nft, &5_001, &12));
+
+    // Data the borrower reported itself, however good, is never auto-approved
+    let self_minted = s.nft.mint(&s.borrower, &10_000, &100, &24, &240_000, &Vec::new(&s.env));
+    assert!(!s.loan_manager.qualifies_for_auto_approval(&self_minted, &1_000, &12));
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &self_minted, &1_000, &12);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Pending);
+
+    // A disabled policy approves nothing
+    s.loan_manager.set_underwriting_policy(&s.admin, &UnderwritingPolicy { enabled: false, ..auto_approval_policy(&s.env) });
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Pending);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+}
+
+#[test]
+fn test_policy_loans_wait_when_pool_cannot_fund() {
+    let s = setup();
+    s.loan_manager.set_underwriting_policy(&s.admin, &auto_approval_policy(&s.env));
+
+    // The pool has stopped lending, so a qualifying loan is left for manual approval
+    s.pool.pause(&s.admin, &pool::PauseScope::Borrows);
+    assert!(s.loan_manager.qualifies_for_auto_approval(&s.prime_nft, &10_000, &12));
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Pending);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+
+    s.pool.unpause(&s.admin, &pool::PauseScope::Borrows);
+    s.loan_manager.approve_loan(&loan_id);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Active);
+}
+
+#[test]
+fn test_underwriting_policy_validation() {
+    let s = setup();
+    assert!(s.loan_manager.get_underwriting_policy().is_none());
+
+    let policy = auto_approval_policy(&s.env);
+    assert_eq!(
+        s.loan_manager.try_set_underwriting_policy(&s.admin, &UnderwritingPolicy { max_ltv: 10_001, ..policy.clone() }),
+        Err(Ok(Error::InvalidUnderwritingPolicy))
+    );
+
+    let unsorted = Vec::from_array(
+        &s.env,
+        [
+            AmountLimit { min_score: 80, max_amount: 5_000 },
+            AmountLimit { min_score: 90, max_amount: 20_000 },
+        ]
+    );
+    assert_eq!(
+        s.loan_manager.try_set_underwriting_policy(&s.admin, &UnderwritingPolicy { amount_limits: unsorted, ..policy.clone() }),
+        Err(Ok(Error::InvalidUnderwritingPolicy))
+    );
+
+    s.loan_manager.set_underwriting_policy(&s.admin, &policy);
+    assert_eq!(s.loan_manager.get_underwriting_policy(), Some(policy));
+}
+
+#[test]
+fn test_restructure_loan() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+
+    set_time(&s, 30 * DAY);
+    s.loan_manager.make_payment(&loan_id, &932);
+
+    set_time(&s, 63 * DAY + 1);
+    assert_eq!(s.loan_manager.check_delinquency(&loan_id), LoanStatus::Late);
+    s.loan_manager.mark_payment_missed(&loan_id);
+
+    // Lower rate, longer term and two months without payments
+    s.loan_manager.restructure_loan(&s.admin, &loan_id, &18, &1_200, &2);
+
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.status, LoanStatus::Active);
+    assert_eq!(loan.payments_missed, 0);
+    assert_eq!(loan.late_fees, 46);
+    assert_eq!(loan.interest_rate, 1_200);
+    assert_eq!(loan.duration_months, 19);
+    assert_eq!(loan.monthly_payment, amortization::level_payment(9_243, 1_200, 18));
+    assert_eq!(loan.next_payment_due, 63 * DAY + 1 + 3 * 30 * DAY);
+    assert_eq!(
+        loan.restructures,
+        Vec::from_array(
+            &s.env,
+            [
+                Restructure {
+                    timestamp: 63 * DAY + 1,
+                    outstanding_balance: 9_243,
+                    payments_missed: 1,
+                    previous_rate: 2_100,
+                    previous_duration: 12,
+                    previous_payment: 932,
+                    new_rate: 1_200,
+                    new_duration: 18,
+                    deferral_months: 2,
+                },
+            ]
+        )
+    );
+
+    let schedule = s.loan_manager.get_schedule(&loan_id);
+    assert_eq!(schedule.len(), 19);
+    assert_eq!(schedule.get(1).unwrap().number, 2);
+    assert_eq!(schedule.get(1).unwrap().interest, 92);
+    assert_eq!(schedule.last().unwrap().remaining_balance, 0);
+    assert_eq!(s.nft.get_nft_data(&s.subprime_nft).restructure_count, 1);
+
+    // The deferred interest is not capitalized: the rebuilt installments repay only the
+    // balance at restructure time, so lenders absorb the two interest-free months
+    let mut principal = 0;
+    for i in 1..schedule.len() {
+        principal += schedule.get(i).unwrap().principal;
+    }
+    assert_eq!(principal, 9_243);
+
+    // The payment holiday is neither late nor accruing interest
+    set_time(&s, 120 * DAY);
+    assert_eq!(s.loan_manager.check_delinquency(&loan_id), LoanStatus::Active);
+    assert_eq!(s.loan_manager.payoff_quote(&loan_id), 9_243 + 46);
+}
+
+#[test]
+fn test_risk_manager_restructure_policy() {
+    let s = setup();
+    let risk_manager = Address::generate(&s.env);
+    s.loan_manager.grant_role(&Role::RiskManager, &risk_manager);
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+
+    assert_eq!(
+        s.loan_manager.try_restructure_loan(&Address::generate(&s.env), &loan_id, &12, &2_100, &0),
+        Err(Ok(Error::Unauthorized))
+    );
+    // Without a policy only the admin may restructure
+    assert_eq!(
+        s.loan_manager.try_restructure_loan(&risk_manager, &loan_id, &12, &2_100, &0),
+        Err(Ok(Error::RestructureNotEligible))
+    );
+
+    let policy = RestructurePolicy {
+        enabled: true,
+        min_payments_made: 1,
+        max_deferral_months: 2,
+        max_extension_months: 6,
+        max_rate_cut: 500,
+    };
+    assert_eq!(
+        s.loan_manager.try_set_restructure_policy(
+            &risk_manager,
+            &RestructurePolicy { max_deferral_months: 7, ..policy.clone() }
+        ),
+        Err(Ok(Error::InvalidRestructurePolicy))
+    );
+    s.loan_manager.set_restructure_policy(&risk_manager, &policy);
+    assert_eq!(s.loan_manager.get_restructure_policy(), Some(policy.clone()));
+
+    // No installment paid yet
+    assert_eq!(
+        s.loan_manager.try_restructure_loan(&risk_manager, &loan_id, &12, &2_100, &0),
+        Err(Ok(Error::RestructureNotEligible))
+    );
+
+    set_time(&s, 30 * DAY);
+    s.loan_manager.make_payment(&loan_id, &932);
+
+    // Deferral, extension and rate cut are each capped
+    for (duration, rate, deferral) in [(11, 2_100, 3), (18, 2_100, 0), (11, 1_599, 0)] {
+        assert_eq!(
+            s.loan_manager.try_restructure_loan(&risk_manager, &loan_id, &duration, &rate, &deferral),
+            Err(Ok(Error::RestructureNotEligible))
+        );
+    }
+    s.loan_manager.restructure_loan(&risk_manager, &loan_id, &17, &1_600, &2);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).interest_rate, 1_600);
+
+    // A disabled policy stops risk managers but not the admin
+    s.loan_manager.set_restructure_policy(&s.admin, &RestructurePolicy { enabled: false, ..policy });
+    assert_eq!(
+        s.loan_manager.try_restructure_loan(&risk_manager, &loan_id, &16, &1_600, &0),
+        Err(Ok(Error::RestructureNotEligible))
+    );
+    s.loan_manager.restructure_loan(&s.admin, &loan_id, &16, &1_600, &0);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).restructures.len(), 2);
+}
+
+#[test]
+fn test_restructure_rules() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    assert_eq!(
+        s.loan_manager.try_restructure_loan(&s.admin, &loan_id, &12, &2_100, &0),
+        Err(Ok(Error::LoanNotActive))
+    );
+    s.loan_manager.approve_loan(&loan_id);
+
+    assert_eq!(
+        s.loan_manager.try_restructure_loan(&s.admin, &loan_id, &12, &2_101, &0),
+        Err(Ok(Error::InvalidRestructure))
+    );
+    assert_eq!(
+        s.loan_manager.try_restructure_loan(&s.admin, &loan_id, &12, &2_100, &7),
+        Err(Ok(Error::InvalidRestructure))
+    );
+    assert_eq!(
+        s.loan_manager.try_restructure_loan(&s.admin, &loan_id, &0, &2_100, &0),
+        Err(Ok(Error::InvalidDuration))
+    );
+
+    for _ in 0..3 {
+        s.loan_manager.restructure_loan(&s.admin, &loan_id, &12, &2_100, &0);
+    }
+    assert_eq!(
+        s.loan_manager.try_restructure_loan(&s.admin, &loan_id, &12, &2_100, &0),
+        Err(Ok(Error::RestructureLimitReached))
+    );
+
+    // Underwriting can hold restructures against the NFT
+    let policy = UnderwritingPolicy {
+        min_score: 70,
+        max_ltv: 10_000,
+        amount_limits: Vec::from_array(&s.env, [AmountLimit { min_score: 70, max_amount: 20_000 }]),
+        ..auto_approval_policy(&s.env)
+    };
+    s.loan_manager.set_underwriting_policy(&s.admin, &policy);
+    assert!(!s.loan_manager.qualifies_for_auto_approval(&s.subprime_nft, &1_000, &12));
+    s.loan_manager.set_underwriting_policy(&s.admin, &UnderwritingPolicy { max_restructures: 3, ..policy });
+    assert!(s.loan_manager.qualifies_for_auto_approval(&s.subprime_nft, &1_000, &12));
+}
+
+#[test]
+fn test_refinance_after_score_improves() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+    assert_eq!(
+        s.loan_manager.try_refinance(&loan_id, &6),
+        Err(Ok(Error::RefinanceNotCheaper))
+    );
+
+    set_time(&s, 30 * DAY);
+    s.loan_manager.make_payment(&loan_id, &932);
+
+    // A month of on-time remittances lifts the NFT into the prime tier
+    s.nft.update_remittance_data(&s.subprime_nft, &10_000, &130_000);
+    assert_eq!(s.nft.get_nft_data(&s.subprime_nft).reliability_score, 100);
+
+    set_time(&s, 45 * DAY);
+    let utilization = s.pool.get_utilization_rate();
+    let balance = s.token.balance(&s.borrower);
+    let new_id = s.loan_manager.refinance(&loan_id, &6);
+    // Without an underwriting policy the admin approves the new loan
+    assert!(s.env.auths().iter().any(|(address, _)| *address == s.admin));
+
+    let old = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(old.status, LoanStatus::Refinanced);
+    assert_eq!(old.outstanding_balance, 0);
+    assert_eq!(old.total_repaid, 932 + 9_243 + 80);
+    assert_eq!(
+        s.loan_manager.try_make_payment(&loan_id, &100),
+        Err(Ok(Error::LoanNotActive))
+    );
+
+    // Only the half month of accrued interest was paid; the principal rolled over
+    let loan = s.loan_manager.get_loan(&new_id);
+    assert_eq!(loan.status, LoanStatus::Active);
+    assert_eq!(loan.loan_amount, 9_243);
+    assert_eq!(loan.duration_months, 6);
+    assert_eq!(loan.interest_rate, s.pool.get_borrow_rate(&0));
+    assert_eq!(loan.next_payment_due, 75 * DAY);
+    assert_eq!(s.token.balance(&s.borrower), balance - 80);
+    assert_eq!(s.pool.get_utilization_rate(), utilization);
+
+    let nft = s.nft.get_nft_data(&s.subprime_nft);
+    assert!(nft.is_staked);
+    assert_eq!(nft.staked_in_loan, new_id);
+    assert_eq!(s.loan_manager.get_active_loan_for_nft(&s.subprime_nft).unwrap().loan_id, new_id);
+
+    // Paying off the new loan settles everything the pool lent
+    s.loan_manager.payoff(&new_id);
+    assert_eq!(s.pool.get_utilization_rate(), 0);
+    assert!(!s.nft.get_nft_data(&s.subprime_nft).is_staked);
+}
+
+#[test]
+fn test_refinance_requires_current_loan() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    assert_eq!(s.loan_manager.try_refinance(&loan_id, &6), Err(Ok(Error::LoanNotActive)));
+    s.loan_manager.approve_loan(&loan_id);
+    s.nft.update_remittance_data(&s.subprime_nft, &10_000, &130_000);
+
+    assert_eq!(s.loan_manager.try_refinance(&loan_id, &0), Err(Ok(Error::InvalidDuration)));
+
+    // A late loan must catch up before it can refinance
+    set_time(&s, 33 * DAY + 1);
+    s.loan_manager.check_delinquency(&loan_id);
+    assert_eq!(s.loan_manager.try_refinance(&loan_id, &6), Err(Ok(Error::LoanNotActive)));
+
+    // The new principal must still fit the NFT's collateral value
+    s.loan_manager.make_payment(&loan_id, &(46 + 932));
+    assert_eq!(
+        s.loan_manager.try_refinance(&loan_id, &1),
+        Err(Ok(Error::LoanExceedsCollateral))
+    );
+    assert!(s.loan_manager.try_refinance(&loan_id, &12).is_ok());
+}
+
+#[test]
+fn test_refinance_goes_through_approval() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+    set_time(&s, 30 * DAY);
+    s.loan_manager.make_payment(&loan_id, &932);
+    s.nft.update_remittance_data(&s.subprime_nft, &10_000, &130_000);
+
+    s.loan_manager.pause(&s.admin, &PauseScope::Approvals);
+    assert_eq!(s.loan_manager.try_refinance(&loan_id, &12), Err(Ok(Error::Paused)));
+    s.loan_manager.unpause(&s.admin, &PauseScope::Approvals);
+
+    // Inside the underwriting policy the borrower refinances alone
+    s.loan_manager.set_underwriting_policy(&s.admin, &auto_approval_policy(&s.env));
+    let new_id = s.loan_manager.refinance(&loan_id, &12);
+    assert!(!s.env.auths().iter().any(|(address, _)| *address == s.admin));
+    assert_eq!(s.loan_manager.get_loan(&new_id).status, LoanStatus::Active);
+}
+
+#[test]
+fn test_borrower_loan_history() {
+    let s = setup();
+
+    let first = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &1_000, &12);
+    let second = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &1_000, &12);
+    s.loan_manager.cancel_loan(&first);
+    let third = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &1_000, &12);
+
+    assert_eq!(s.loan_manager.get_borrower_loan_count(&s.borrower), 3);
+    let page = s.loan_manager.get_borrower_loans(&s.borrower, &0, &2);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(0).unwrap().loan_id, first);
+    assert_eq!(page.get(0).unwrap().status, LoanStatus::Cancelled);
+    assert_eq!(page.get(1).unwrap().loan_id, second);
+
+    let page = s.loan_manager.get_borrower_loans(&s.borrower, &2, &10);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().loan_id, third);
+    assert!(s.loan_manager.get_borrower_loans(&s.borrower, &5, &10).is_empty());
+    assert!(s.loan_manager.get_borrower_loans(&Address::generate(&s.env), &0, &10).is_empty());
+
+    let loans = s.loan_manager.get_loans(&Vec::from_array(&s.env, [third, first]));
+    assert_eq!(loans.get(0).unwrap().loan_id, third);
+    assert_eq!(loans.get(1).unwrap().loan_id, first);
+    assert_eq!(
+        s.loan_manager.try_get_loans(&Vec::from_array(&s.env, [first, 99])),
+        Err(Ok(Error::LoanNotFound))
+    );
+
+    let mut too_many = Vec::new(&s.env);
+    for _ in 0..=MAX_PAGE_SIZE {
+        too_many.push_back(first);
+    }
+    assert_eq!(s.loan_manager.try_get_loans(&too_many), Err(Ok(Error::PageTooLarge)));
+
+    // Entries missing from storage are skipped rather than failing the page
+    s.env.as_contract(&s.loan_manager.address, || {
+        storage::remove(&s.env, &DataKey::Loan(second));
+        storage::remove(&s.env, &DataKey::BorrowerLoan(s.borrower.clone(), 2));
+    });
+    let page = s.loan_manager.get_borrower_loans(&s.borrower, &0, &10);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().loan_id, first);
+}
+
+#[test]
+fn test_list_loans_by_status() {
+    let s = setup();
+    s.env.cost_estimate().budget().reset_unlimited();
+
+    // 105 cancelled requests, then a pending one after the first scan window
+    for _ in 0..105 {
+        let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &1_000, &12);
+        s.loan_manager.cancel_loan(&loan_id);
+    }
+    let pending = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &1_000, &12);
+
+    let page = s.loan_manager.list_loans_by_status(&LoanStatus::Cancelled, &0, &10);
+    assert_eq!(page.loans.len(), 10);
+    assert_eq!(page.loans.get(9).unwrap().loan_id, 10);
+    assert_eq!(page.next_cursor, Some(10));
+
+    let page = s.loan_manager.list_loans_by_status(&LoanStatus::Cancelled, &100, &100);
+    assert_eq!(page.loans.len(), 5);
+    assert_eq!(page.next_cursor, None);
+
+    // A scan window with no matches still moves the cursor along
+    let page = s.loan_manager.list_loans_by_status(&LoanStatus::Pending, &0, &10);
+    assert!(page.loans.is_empty());
+    assert_eq!(page.next_cursor, Some(100));
+
+    let page = s.loan_manager.list_loans_by_status(&LoanStatus::Pending, &100, &10);
+    assert_eq!(page.loans.len(), 1);
+    assert_eq!(page.loans.get(0).unwrap().loan_id, pending);
+    assert_eq!(page.next_cursor, None);
+
+    // Lapsed requests are listed as expired without waiting for `expire_loan`
+    let ttl = s.loan_manager.get_pending_loan_ttl();
+    s.env.ledger().with_mut(|l| l.sequence_number += ttl + 1);
+    let page = s.loan_manager.list_loans_by_status(&LoanStatus::Expired, &100, &10);
+    assert_eq!(page.loans.get(0).unwrap().loan_id, pending);
+
+    // Ids whose loan is missing from storage are skipped
+    s.env.as_contract(&s.loan_manager.address, || storage::remove(&s.env, &DataKey::Loan(101)));
+    let page = s.loan_manager.list_loans_by_status(&LoanStatus::Cancelled, &100, &100);
+    assert_eq!(page.loans.len(), 4);
+    assert_eq!(page.loans.get(0).unwrap().loan_id, 102);
+}
+
+#[test]
+fn test_loans_live_in_persistent_storage() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &1_000, &12);
+    let ttl = |key: DataKey| {
+        s.env.as_contract(&s.loan_manager.address, || s.env.storage().persistent().get_ttl(&key))
+    };
+    assert_eq!(ttl(DataKey::Loan(loan_id)), storage::PERSISTENT_BUMP_AMOUNT);
+    assert_eq!(ttl(DataKey::Schedule(loan_id)), storage::PERSISTENT_BUMP_AMOUNT);
+
+    // Forty days on, a keeper tops the entries back up without any loan activity
+    s.env.ledger().with_mut(|l| l.sequence_number += 40 * 17_280);
+    assert_eq!(ttl(DataKey::Loan(loan_id)), storage::PERSISTENT_BUMP_AMOUNT - 40 * 17_280);
+
+    let keys = Vec::from_array(
+        &s.env,
+        [DataKey::Loan(loan_id), DataKey::Schedule(loan_id), DataKey::Loan(99), DataKey::LoanCounter]
+    );
+    assert_eq!(s.loan_manager.bump(&keys), 2);
+    assert_eq!(ttl(DataKey::Loan(loan_id)), storage::PERSISTENT_BUMP_AMOUNT);
+    assert_eq!(ttl(DataKey::Schedule(loan_id)), storage::PERSISTENT_BUMP_AMOUNT);
+
+    let instance_ttl = s.env.as_contract(&s.loan_manager.address, || s.env.storage().instance().get_ttl());
+    assert_eq!(instance_ttl, 30 * 17_280);
+}
+
+#[test]
+fn test_upgrade_keeps_loans_and_nfts() {
+    let s = setup();
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+    set_time(&s, 30 * DAY);
+    s.loan_manager.make_payment(&loan_id, &932);
+
+    let loan = s.loan_manager.get_loan(&loan_id);
+    let schedule = s.loan_manager.get_schedule(&loan_id);
+    let nft = s.nft.get_nft_data(&s.subprime_nft);
+    let utilization = s.pool.get_utilization_rate();
+
+    assert_eq!(s.loan_manager.schema_version(), 1);
+    assert_eq!(s.nft.schema_version(), 1);
+    assert_eq!(s.pool.schema_version(), 1);
+
+    let loan_manager_hash = s.env.deployer().upload_contract_wasm(loan_manager_wasm::WASM);
+    let nft_hash = s.env.deployer().upload_contract_wasm(nft::WASM);
+    let pool_hash = s.env.deployer().upload_contract_wasm(pool::WASM);
+    s.loan_manager.upgrade(&loan_manager_hash);
+    s.nft.upgrade(&nft_hash);
+    s.pool.upgrade(&pool_hash);
+
+    // Same data, and still in the current layout
+    assert_eq!(s.loan_manager.get_loan(&loan_id), loan);
+    assert_eq!(s.loan_manager.get_schedule(&loan_id), schedule);
+    assert_eq!(s.nft.get_nft_data(&s.subprime_nft).reliability_score, nft.reliability_score);
+    assert_eq!(s.nft.get_nft_data(&s.subprime_nft).staked_in_loan, loan_id);
+    assert_eq!(s.pool.get_utilization_rate(), utilization);
+    assert_eq!(s.loan_manager.migrate(), 1);
+
+    // The upgraded contracts keep working together
+    s.loan_manager.make_payment(&loan_id, &932);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).payments_made, 2);
+}
+
+#[test]
+fn test_upgrade_requires_admin() {
+    let s = setup();
+    let hash = s.env.deployer().upload_contract_wasm(loan_manager_wasm::WASM);
+
+    s.env.mock_auths(&[MockAuth {
+        address: &s.borrower,
+        invoke: &MockAuthInvoke {
+            contract: &s.loan_manager.address,
+            fn_name: "upgrade",
+            args: (hash.clone(),).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.loan_manager.try_upgrade(&hash).is_err());
+
+    s.env.mock_auths(&[MockAuth {
+        address: &s.borrower,
+        invoke: &MockAuthInvoke {
+            contract: &s.loan_manager.address,
+            fn_name: "migrate",
+            args: ().into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.loan_manager.try_migrate().is_err());
+}
+
+#[test]
+fn test_migrate_moves_instance_storage_to_persistent() {
+    let s = setup();
+
+    // 55 closed requests and one active loan, then rewritten into the original layout
+    for _ in 0..55 {
+        let loan_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &1_000, &12);
+        s.loan_manager.cancel_loan(&loan_id);
+    }
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &1_000, &12);
+    s.loan_manager.approve_loan(&loan_id);
+    let loan = s.loan_manager.get_loan(&loan_id);
+    let schedule = s.loan_manager.get_schedule(&loan_id);
+
+    s.env.as_contract(&s.loan_manager.address, || {
+        let persistent = s.env.storage().persistent();
+        let instance = s.env.storage().instance();
+        let mut loan_ids = Vec::new(&s.env);
+        for id in 1..=loan_id {
+            let loan: Loan = persistent.get(&DataKey::Loan(id)).unwrap();
+            instance.set(&DataKey::Loan(id), &LegacyLoan {
+                loan_id: id,
+                borrower: loan.borrower,
+                nft_collateral_id: loan.nft_collateral_id,
+                loan_amount: loan.loan_amount,
+                outstanding_balance: loan.outstanding_balance,
+                total_repaid: loan.total_repaid,
+                interest_rate: loan.interest_rate,
+                duration_months: loan.duration_months,
+                monthly_payment: loan.monthly_payment,
+                start_timestamp: loan.start_timestamp,
+                next_payment_due: loan.next_payment_due,
+                status: loan.status,
+                payments_made: loan.payments_made,
+                payments_missed: loan.payments_missed,
+            });
+            persistent.remove(&DataKey::Loan(id));
+            persistent.remove(&DataKey::Schedule(id));
+            persistent.remove(&DataKey::BorrowerLoan(s.borrower.clone(), id as u32 - 1));
+            loan_ids.push_back(id);
+        }
+        instance.set(&DataKey::BorrowerLoans(s.borrower.clone()), &loan_ids);
+        persistent.remove(&DataKey::BorrowerLoanCount(s.borrower.clone()));
+        persistent.remove(&DataKey::NftLoan(s.prime_nft));
+        persistent.remove(&DataKey::NftLoan(s.subprime_nft));
+        instance.remove(&DataKey::SchemaVersion);
+    });
+    assert_eq!(s.loan_manager.schema_version(), 0);
+
+    // Twenty loans per call
+    assert_eq!(s.loan_manager.migrate(), 0);
+    assert_eq!(s.loan_manager.migrate(), 0);
+    assert_eq!(s.loan_manager.migrate(), 1);
+    assert_eq!(s.loan_manager.migrate(), 1);
+
+    s.env.as_contract(&s.loan_manager.address, || {
+        for key in [
+            DataKey::Loan(1),
+            DataKey::Loan(loan_id),
+            DataKey::Schedule(loan_id),
+            DataKey::NftLoan(s.prime_nft),
+            DataKey::BorrowerLoanCount(s.borrower.clone()),
+            DataKey::BorrowerLoan(s.borrower.clone(), 55),
+        ] {
+            assert!(s.env.storage().persistent().has(&key));
+            assert!(!s.env.storage().instance().has(&key));
+        }
+        assert!(!s.env.storage().instance().has(&DataKey::BorrowerLoans(s.borrower.clone())));
+        assert!(!s.env.storage().instance().has(&DataKey::MigrationCursor));
+    });
+
+    let migrated = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(migrated.status, LoanStatus::Active);
+    assert_eq!(migrated.outstanding_balance, loan.outstanding_balance);
+    assert_eq!(migrated.monthly_payment, loan.monthly_payment);
+    assert_eq!(s.loan_manager.get_schedule(&loan_id), schedule);
+    assert_eq!(s.loan_manager.get_borrower_loans(&s.borrower, &55, &1).get(0).unwrap(), migrated);
+    assert_eq!(s.loan_manager.get_active_loan_for_nft(&s.prime_nft), Some(migrated));
+    assert_eq!(s.loan_manager.get_active_loan_for_nft(&s.subprime_nft), None);
+    s.loan_manager.make_payment(&loan_id, &100);
+}
+
+#[test]
+fn test_migrate_converts_original_deployment() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let admin = Address::generate(&env);
+    let lender = Address::generate(&env);
+    let late_lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let token_admin = StellarAssetClient::new(&env, &usdc.address());
+    token_admin.mint(&lender, &50_000);
+    token_admin.mint(&late_lender, &10_000);
+    token_admin.mint(&borrower, &5_000);
+
+    // An active loan with one payment made and a pending request, on the original contracts,
+    // and a lender who joined after that payment's interest was booked
+    let nft_id = env.register(baseline::nft::WASM, ());
+    let pool_id = env.register(baseline::pool::WASM, ());
+    let loan_manager_id = env.register(baseline::loan_manager::WASM, ());
+    let oracle_id = env.register(MockOracle, ());
+
+    let old_nft = baseline::nft::Client::new(&env, &nft_id);
+    let old_pool = baseline::pool::Client::new(&env, &pool_id);
+    let old_loan_manager = baseline::loan_manager::Client::new(&env, &loan_manager_id);
+
+    old_nft.initialize(&admin, &oracle_id, &loan_manager_id);
+    old_pool.initialize(&admin, &loan_manager_id, &usdc.address(), &500);
+    old_loan_manager.initialize(&admin, &nft_id, &pool_id, &oracle_id, &usdc.address());
+    old_pool.deposit(&lender, &50_000);
+
+    let active_nft = old_nft.mint(&borrower, &10_000, &95, &12, &120_000, &Vec::new(&env));
+    let pending_nft = old_nft.mint(&borrower, &10_000, &95, &12, &120_000, &Vec::new(&env));
+    let active_id = old_loan_manager.request_loan(&borrower, &active_nft, &10_000, &12);
+    old_loan_manager.approve_loan(&active_id);
+    old_loan_manager.make_payment(&active_id, &1_000);
+    let pending_id = old_loan_manager.request_loan(&borrower, &pending_nft, &5_000, &12);
+    old_pool.deposit(&late_lender, &10_000);
+
+    let old_loan = old_loan_manager.get_loan(&active_id);
+    let interest_paid = 1_000 - (old_loan.loan_amount - old_loan.outstanding_balance);
+    assert_eq!(interest_paid, 125);
+
+    // Swap in the current code; the original contracts have no `upgrade` entrypoint
+    for (id, wasm) in [
+        (&loan_manager_id, loan_manager_wasm::WASM),
+        (&nft_id, nft::WASM),
+        (&pool_id, pool::WASM),
+    ] {
+        let hash = env.deployer().upload_contract_wasm(wasm);
+        env.as_contract(id, || env.deployer().update_current_contract_wasm(hash));
+    }
+    let loan_manager = LoanManagerClient::new(&env, &loan_manager_id);
+    let nft = nft::Client::new(&env, &nft_id);
+    let pool = pool::Client::new(&env, &pool_id);
+
+    assert_eq!(loan_manager.migrate(), 1);
+    assert_eq!(pool.migrate(), 1);
+
+    // The original NFT contract never stored its admin and takes the LoanManager's
+    assert_eq!(nft.get_admin(), None);
+    assert_eq!(nft.migrate(), 1);
+    assert_eq!(nft.get_admin(), Some(admin.clone()));
+
+    // The active loan keeps its terms, with the rest of its principal re-amortized
+    let loan = loan_manager.get_loan(&active_id);
+    assert_eq!(loan.status, LoanStatus::Active);
+    assert_eq!(loan.outstanding_balance, old_loan.outstanding_balance);
+    assert_eq!(loan.total_repaid, 1_000);
+    assert_eq!(loan.interest_rate, old_loan.interest_rate);
+    assert_eq!(loan.duration_months, 12);
+    assert_eq!(loan.payments_made, 1);
+    assert_eq!(loan.installment_paid, 0);
+    assert_eq!(loan.late_fee_installments, 1);
+    assert_eq!(loan.restructures.len(), 0);
+
+    let schedule = loan_manager.get_schedule(&active_id);
+    assert_eq!(schedule.len(), 12);
+    let mut remaining = 0;
+    for installment in schedule.slice(1..).iter() {
+        remaining += installment.principal;
+    }
+    assert_eq!(remaining, loan.outstanding_balance);
+    assert_eq!(loan.monthly_payment, schedule.get(1).unwrap().payment);
+
+    // Borrower listings and the NFT locks are indexed
+    let loans = loan_manager.get_borrower_loans(&borrower, &0, &10);
+    assert_eq!(loans.len(), 2);
+    assert_eq!(loans.get(0).unwrap(), loan);
+    assert_eq!(loan_manager.get_active_loan_for_nft(&active_nft), Some(loan.clone()));
+    assert_eq!(loan_manager.get_active_loan_for_nft(&pending_nft).unwrap().loan_id, pending_id);
+    env.as_contract(&loan_manager_id, || {
+        assert!(!env.storage().instance().has(&DataKey::BorrowerLoans(borrower.clone())));
+    });
+
+    // Deposits become shares 1:1. The original formula also owed the late lender 25 of
+    // interest booked before it joined, but only the 125 the pool still holds is paid out.
+    assert_eq!(pool.total_shares(), 60_000);
+    assert_eq!(pool.get_lender_info(&lender).shares, 50_000);
+    assert_eq!(pool.pending_interest(&lender), 125);
+    assert_eq!(pool.pending_interest(&late_lender), 0);
+    assert_eq!(pool.get_rate_model().base_rate, 500);
+
+    // NFT data written by the original contract is moved and converted
+    let nft_data = nft.get_nft_data(&active_nft);
+    assert!(nft_data.is_staked);
+    assert_eq!(nft_data.staked_in_loan, active_id);
+    assert_eq!(nft_data.restructure_count, 0);
+
+    // The upgraded contracts keep working together
+    loan_manager.make_payment(&active_id, &loan.monthly_payment);
+    assert_eq!(loan_manager.get_loan(&active_id).payments_made, 2);
+    loan_manager.approve_loan(&pending_id);
+    assert_eq!(nft.get_nft_data(&pending_nft).staked_in_loan, pending_id);
+    assert!(pool.claim_interest(&lender) > 125);
+    assert!(pool.claim_interest(&late_lender) > 0);
+}
+
+#[test]
+fn test_admin_transfer_is_two_step() {
+    let s = setup();
+    let new_admin = Address::generate(&s.env);
+
+    s.loan_manager.propose_admin(&new_admin);
+    assert_eq!(s.loan_manager.get_admin(), Some(s.admin.clone()));
+    assert_eq!(s.loan_manager.get_pending_admin(), Some(new_admin.clone()));
+
+    // Only the nominee can accept
+    s.env.mock_auths(&[MockAuth {
+        address: &s.admin,
+        invoke: &MockAuthInvoke {
+            contract: &s.loan_manager.address,
+            fn_name: "accept_admin",
+            args: ().into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.loan_manager.try_accept_admin().is_err());
+
+    s.env.mock_all_auths_allowing_non_root_auth();
+    s.loan_manager.accept_admin();
+    assert_eq!(s.loan_manager.get_admin(), Some(new_admin.clone()));
+    assert_eq!(s.loan_manager.get_pending_admin(), None);
+    assert_eq!(s.loan_manager.try_accept_admin(), Err(Ok(Error::NoPendingAdmin)));
+
+    // The new admin, not the old one, now approves loans
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    s.env.mock_auths(&[MockAuth {
+        address: &s.admin,
+        invoke: &MockAuthInvoke {
+            contract: &s.loan_manager.address,
+            fn_name: "approve_loan",
+            args: (loan_id,).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.loan_manager.try_approve_loan(&loan_id).is_err());
+
+    s.env.mock_all_auths_allowing_non_root_auth();
+    s.loan_manager.approve_loan(&loan_id);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Active);
+}
+
+#[test]
+fn test_risk_manager_role() {
+    let s = setup();
+    let risk_manager = Address::generate(&s.env);
+
+    assert_eq!(s.loan_manager.try_set_max_ltv(&risk_manager, &5_000), Err(Ok(Error::Unauthorized)));
+
+    s.loan_manager.grant_role(&Role::RiskManager, &risk_manager);
+    assert!(s.loan_manager.has_role(&Role::RiskManager, &risk_manager));
+    assert!(!s.loan_manager.has_role(&Role::Pauser, &risk_manager));
+    assert!(s.loan_manager.has_role(&Role::Pauser, &s.admin));
+
+    // Grants and the admin sit under the keys the contract's own DataKey used before
+    // access control moved to the common crate
+    #[contracttype]
+    enum LegacyKey {
+        AdminAddress,
+        RoleMember(Role, Address),
+    }
+    s.env.as_contract(&s.loan_manager.address, || {
+        let admin: Option<Address> = s.env.storage().instance().get(&LegacyKey::AdminAddress);
+        assert_eq!(admin, Some(s.admin.clone()));
+        assert!(s.env.storage().instance().has(&LegacyKey::RoleMember(Role::RiskManager, risk_manager.clone())));
+    });
+
+    s.loan_manager.set_max_ltv(&risk_manager, &5_000);
+    assert_eq!(s.loan_manager.get_max_ltv(), 5_000);
+
+    // Risk managers cannot hand out roles themselves
+    let other = Address::generate(&s.env);
+    s.env.mock_auths(&[MockAuth {
+        address: &risk_manager,
+        invoke: &MockAuthInvoke {
+            contract: &s.loan_manager.address,
+            fn_name: "grant_role",
+            args: (Role::RiskManager, other.clone()).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.loan_manager.try_grant_role(&Role::RiskManager, &other).is_err());
+
+    s.env.mock_all_auths_allowing_non_root_auth();
+    s.loan_manager.revoke_role(&Role::RiskManager, &risk_manager);
+    assert_eq!(s.loan_manager.try_set_max_ltv(&risk_manager, &6_000), Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn test_renounce_admin() {
+    let s = setup();
+    let risk_manager = Address::generate(&s.env);
+    s.loan_manager.grant_role(&Role::RiskManager, &risk_manager);
+    s.loan_manager.propose_admin(&Address::generate(&s.env));
+
+    s.loan_manager.renounce_admin();
+    assert_eq!(s.loan_manager.get_admin(), None);
+    assert_eq!(s.loan_manager.get_pending_admin(), None);
+    assert!(!s.loan_manager.has_role(&Role::RiskManager, &s.admin));
+
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    assert_eq!(s.loan_manager.try_approve_loan(&loan_id), Err(Ok(Error::Unauthorized)));
+    assert_eq!(s.loan_manager.try_propose_admin(&s.admin), Err(Ok(Error::Unauthorized)));
+    assert_eq!(s.loan_manager.try_accept_admin(), Err(Ok(Error::NoPendingAdmin)));
+
+    // Roles granted earlier keep working
+    s.loan_manager.set_max_ltv(&risk_manager, &5_000);
+    assert_eq!(s.loan_manager.get_max_ltv(), 5_000);
+}
+
+#[test]
+fn test_initialize_sets_given_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let nft = Address::generate(&env);
+    let pool = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let usdc = Address::generate(&env);
+    let loan_manager = LoanManagerClient::new(&env, &env.register(LoanManager, ()));
+
+    loan_manager.initialize(&admin, &nft, &pool, &oracle, &usdc);
+    assert_eq!(loan_manager.get_admin(), Some(admin.clone()));
+    assert_eq!(
+        loan_manager.try_initialize(&admin, &nft, &pool, &oracle, &usdc),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+}
+
+#[test]
+fn test_pause_requests_and_approvals() {
+    let s = setup();
+    let pauser = Address::generate(&s.env);
+    s.loan_manager.grant_role(&Role::Pauser, &pauser);
+
+    let active_id = s.loan_manager.request_loan(&s.borrower, &s.prime_nft, &10_000, &12);
+    s.loan_manager.approve_loan(&active_id);
+    let pending_id = s.loan_manager.request_loan(&s.borrower, &s.subprime_nft, &5_000, &12);
+
+    s.loan_manager.pause(&pauser, &PauseScope::Requests);
+    s.loan_manager.pause(&pauser, &PauseScope::Approvals);
+    assert_eq!(
+        s.loan_manager.try_request_loan(&s.borrower, &s.subprime_nft, &5_000, &12),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(s.loan_manager.try_approve_loan(&pending_id), Err(Ok(Error::Paused)));
+    assert_eq!(s.loan_manager.try_refinance(&active_id, &12), Err(Ok(Error::Paused)));
+
+    // Borrowers can always repay
+    s.loan_manager.make_payment(&active_id, &861);
+    assert_eq!(s.loan_manager.get_loan(&active_id).payments_made, 1);
+
+    // With only approvals paused, a loan inside the policy waits for approval
+    s.loan_manager.unpause(&pauser, &PauseScope::Requests);
+    s.loan_manager.set_underwriting_policy(&s.admin, &auto_approval_policy(&s.env));
+    let prime_nft = s.nft.mint(&s.borrower, &10_000, &95, &12, &120_000, &Vec::new(&s.env));
+    let loan_id = s.loan_manager.request_loan(&s.borrower, &prime_nft, &5_000, &12);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Pending);
+    assert!(s.loan_manager.is_paused(&PauseScope::Approvals));
+    assert!(!s.loan_manager.is_paused(&PauseScope::Requests));
+
+    s.loan_manager.unpause(&pauser, &PauseScope::Approvals);
+    s.loan_manager.approve_loan(&loan_id);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).status, LoanStatus::Active);
+}
@@ -1,5 +1,25 @@
 #![no_std]
-use soroban_sdk::{ contract, contractimpl, contracttype, Address, String, Env, Vec };
+use soroban_sdk::{
+    contract,
+    contracterror,
+    contractevent,
+    contractimpl,
+    contracttype,
+    Address,
+    BytesN,
+    String,
+    Env,
+    Vec,
+};
+
+use common::access::{ self, AccessError };
+use common::storage;
+
+// Storage layout version written by `initialize` and brought up to date by `migrate`
+const SCHEMA_VERSION: u32 = 1;
+
+// Loan ids `migrate` checks per call
+const MIGRATION_BATCH: u64 = 50;
 
 #[contracttype]
 #[derive(Clone)]
@@ -12,7 +32,7 @@ pub struct VerificationRequest {
 }
 
 #[contracttype]
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum VerificationStatus {
     Pending = 0,
     Verified = 1,
@@ -26,7 +46,27 @@ pub struct PaymentRecord {
     pub paid: bool,
 }
 
+// Oracle operations that can be paused on their own
 #[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PauseScope {
+    /// Operator submissions: verifications, remittances and missed payments
+    Reports,
+}
+
+// Duties the admin can hand out without giving away the admin key.
+// The admin itself may act in every role.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    /// Pauses and resumes remittance reporting
+    Pauser,
+    /// Adds and removes oracle operators
+    OperatorManager,
+}
+
+#[contracttype]
+#[derive(Clone)]
 pub enum DataKey {
     VerificationRequest(Address),
     OracleOperators(u32), // Index -> Address of authorized operators
@@ -34,6 +74,124 @@ pub enum DataKey {
     RemittanceNFTContract,
     LoanManagerContract,
     MonitoredLoans(u64), // loan_id -> bool (is being monitored)
+    Paused(PauseScope), // present while the scope is paused
+    SchemaVersion,
+    MigrationCursor, // last loan id an unfinished migration step has checked
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    /// The contract has not been initialized yet
+    NotInitialized = 1,
+    /// The caller is not a registered oracle operator
+    UnauthorizedOperator = 2,
+    /// The user has no pending verification request
+    VerificationNotFound = 3,
+    /// The verification request has already been processed
+    AlreadyProcessed = 4,
+    /// The loan is not being monitored for remittances
+    LoanNotMonitored = 5,
+    /// The reported user is not the loan's borrower
+    BorrowerMismatch = 6,
+    /// The reported NFT is not the loan's collateral
+    CollateralMismatch = 7,
+    /// The contract has already been initialized
+    AlreadyInitialized = 8,
+    /// The caller is not the admin and does not hold the required role
+    Unauthorized = 9,
+    /// No admin transfer has been proposed
+    NoPendingAdmin = 10,
+    /// The operation is paused
+    Paused = 11,
+}
+
+impl From<AccessError> for Error {
+    fn from(error: AccessError) -> Self {
+        match error {
+            AccessError::Unauthorized => Error::Unauthorized,
+            AccessError::NoPendingAdmin => Error::NoPendingAdmin,
+        }
+    }
+}
+
+#[contractevent(topics = ["role_granted"], data_format = "single-value")]
+pub struct RoleGrantedEvent {
+    #[topic]
+    pub role: Role,
+    pub account: Address,
+}
+
+#[contractevent(topics = ["role_revoked"], data_format = "single-value")]
+pub struct RoleRevokedEvent {
+    #[topic]
+    pub role: Role,
+    pub account: Address,
+}
+
+#[contractevent(topics = ["verification_requested"], data_format = "single-value")]
+pub struct VerificationRequestedEvent {
+    pub user: Address,
+}
+
+#[contractevent(topics = ["verification_complete"], data_format = "single-value")]
+pub struct VerificationCompleteEvent {
+    #[topic]
+    pub user: Address,
+    pub reliability_score: u32,
+}
+
+#[contractevent(topics = ["monitoring_started"], data_format = "single-value")]
+pub struct MonitoringStartedEvent {
+    pub loan_id: u64,
+}
+
+#[contractevent(topics = ["remittance_reported"])]
+pub struct RemittanceReportedEvent {
+    #[topic]
+    pub loan_id: u64,
+    #[topic]
+    pub nft_id: u64,
+    pub amount: i128,
+    pub remaining: i128,
+}
+
+#[contractevent(topics = ["operator_added"], data_format = "single-value")]
+pub struct OperatorAddedEvent {
+    pub operator: Address,
+}
+
+#[contractevent(topics = ["operator_removed"], data_format = "single-value")]
+pub struct OperatorRemovedEvent {
+    pub operator: Address,
+}
+
+#[contractevent(topics = ["paused"], data_format = "single-value")]
+pub struct PausedEvent {
+    pub scope: PauseScope,
+}
+
+#[contractevent(topics = ["unpaused"], data_format = "single-value")]
+pub struct UnpausedEvent {
+    pub scope: PauseScope,
+}
+
+#[contractevent(topics = ["upgraded"], data_format = "single-value")]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+}
+
+#[contractevent(topics = ["migrated"], data_format = "single-value")]
+pub struct MigratedEvent {
+    pub schema_version: u32,
+}
+
+#[contractevent(topics = ["payment_missed_reported"], data_format = "single-value")]
+pub struct PaymentMissedReportedEvent {
+    #[topic]
+    pub loan_id: u64,
+    pub nft_id: u64,
 }
 
 mod remittance {
@@ -53,12 +211,24 @@ pub struct OracleVerifier;
 
 #[contractimpl]
 impl OracleVerifier {
-    pub fn __initialize(
+    // Public initialize function that can be called after deployment
+    pub fn initialize(
         env: Env,
+        admin: Address,
         nft_contract: Address,
         loan_manager: Address,
         operators: Vec<Address>
-    ) {
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        admin.require_auth();
+
+        // Only allow initialization if not already initialized
+        if env.storage().instance().has(&DataKey::RemittanceNFTContract) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        access::set_admin(&env, &admin);
         env.storage().instance().set(&DataKey::RemittanceNFTContract, &nft_contract);
         env.storage().instance().set(&DataKey::LoanManagerContract, &loan_manager);
 
@@ -68,9 +238,160 @@ impl OracleVerifier {
             env.storage().instance().set(&DataKey::OracleOperators(i), &operators.get(i).unwrap());
         }
         env.storage().instance().set(&DataKey::OracleOperatorCount, &count);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+
+        Ok(())
+    }
+
+    // Admin replaces the contract's code. Data is left as is; call `migrate` afterwards
+    // if the new code expects a newer storage layout.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        UpgradedEvent { wasm_hash: new_wasm_hash }.publish(&env);
+
+        Ok(())
+    }
+
+    // Storage layout version the contract's data is in; 0 for deployments that predate versioning
+    pub fn schema_version(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::SchemaVersion).unwrap_or(0)
+    }
+
+    // Admin brings stored data up to SCHEMA_VERSION and returns the version it is in afterwards.
+    // The original contract had no admin, so the first migration adopts the LoanManager's.
+    pub fn migrate(env: Env) -> Result<u32, Error> {
+        storage::extend_instance(&env);
+
+        Self::adopt_legacy_admin(&env);
+        access::require_admin(&env)?;
+
+        let mut version = Self::schema_version(env.clone());
+
+        // 0 -> 1: monitored loans move from instance to persistent storage. Verification
+        // requests are keyed by address and move through `migrate_verification_requests`.
+        if version == 0 {
+            if !Self::migrate_to_v1(&env) {
+                return Ok(version);
+            }
+            version = 1;
+            env.storage().instance().set(&DataKey::SchemaVersion, &version);
+            MigratedEvent { schema_version: version }.publish(&env);
+        }
+
+        Ok(version)
+    }
+
+    // Admin moves the verification requests of `users` that the original contract kept in
+    // instance storage. They can't be listed on chain, so the admin reads the addresses off
+    // the contract instance. Returns how many were moved.
+    pub fn migrate_verification_requests(env: Env, users: Vec<Address>) -> Result<u32, Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        let mut moved = 0;
+        for user in users.iter() {
+            if storage::migrate(&env, &DataKey::VerificationRequest(user)) {
+                moved += 1;
+            }
+        }
+        Ok(moved)
+    }
+
+    pub fn get_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::admin(&env)
+    }
+
+    // Successor named by `propose_admin` who has not accepted yet
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::pending_admin(&env)
+    }
+
+    // Admin nominates a successor, who takes over by calling `accept_admin`
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::propose_admin(&env, &new_admin)?)
+    }
+
+    // The nominated successor takes over as admin
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::accept_admin(&env)?)
+    }
+
+    // Admin gives up control for good; admin-only calls fail from then on
+    pub fn renounce_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::renounce_admin(&env)?)
+    }
+
+    // Admin lets `account` act in `role`
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::grant_role(&env, role, &account)?;
+        RoleGrantedEvent { role, account }.publish(&env);
+        Ok(())
+    }
+
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::revoke_role(&env, role, &account)?;
+        RoleRevokedEvent { role, account }.publish(&env);
+        Ok(())
+    }
+
+    // Whether `account` may act in `role`; the admin may act in every role
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        storage::extend_instance(&env);
+
+        access::has_role(&env, role, &account)
+    }
+
+    // Pauser stops `scope` until it is unpaused; borrowers can still repay the loan manager directly
+    pub fn pause(env: Env, caller: Address, scope: PauseScope) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::Pauser, &caller)?;
+
+        env.storage().instance().set(&DataKey::Paused(scope), &true);
+        PausedEvent { scope }.publish(&env);
+        Ok(())
+    }
+
+    pub fn unpause(env: Env, caller: Address, scope: PauseScope) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::Pauser, &caller)?;
+
+        env.storage().instance().remove(&DataKey::Paused(scope));
+        UnpausedEvent { scope }.publish(&env);
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env, scope: PauseScope) -> bool {
+        storage::extend_instance(&env);
+
+        env.storage().instance().has(&DataKey::Paused(scope))
     }
 
     pub fn request_verification(env: Env, user: Address, provider: String, account_id: String) {
+        storage::extend_instance(&env);
+
         user.require_auth();
 
         let request = VerificationRequest {
@@ -81,9 +402,9 @@ impl OracleVerifier {
             status: VerificationStatus::Pending,
         };
 
-        env.storage().instance().set(&DataKey::VerificationRequest(user.clone()), &request);
+        storage::write(&env, &DataKey::VerificationRequest(user.clone()), &request);
 
-        env.events().publish(("verification_requested",), user);
+        VerificationRequestedEvent { user }.publish(&env);
     }
 
     // Oracle operator submits verification result
@@ -95,18 +416,20 @@ impl OracleVerifier {
         history_months: u32,
         total_sent: i128,
         payment_history: Vec<remittance::PaymentRecord>
-    ) {
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
         // Verify operator is authorized
-        Self::verify_operator(&env, &operator);
+        Self::verify_operator(&env, &operator)?;
+        Self::require_not_paused(&env, PauseScope::Reports)?;
         operator.require_auth();
 
-        let mut request: VerificationRequest = env
-            .storage()
-            .instance()
-            .get(&DataKey::VerificationRequest(user.clone()))
-            .expect("No verification request found");
+        let mut request: VerificationRequest = storage::read(&env, &DataKey::VerificationRequest(user.clone()))
+            .ok_or(Error::VerificationNotFound)?;
 
-        assert!(request.status == VerificationStatus::Pending, "Already processed");
+        if request.status != VerificationStatus::Pending {
+            return Err(Error::AlreadyProcessed);
+        }
 
         // Calculate reliability score
         let reliability_score = Self::calculate_reliability_score(&payment_history);
@@ -120,7 +443,7 @@ impl OracleVerifier {
 
         // In real implementation:
         let nft_client = remittance::Client::new(&env, &nft_contract);
-        let token_id = nft_client.mint(
+        nft_client.mint_verified(
             &user,
             &monthly_amount,
             &reliability_score,
@@ -131,26 +454,33 @@ impl OracleVerifier {
 
         // Update request status
         request.status = VerificationStatus::Verified;
-        env.storage().instance().set(&DataKey::VerificationRequest(user.clone()), &request);
+        storage::write(&env, &DataKey::VerificationRequest(user.clone()), &request);
+
+        VerificationCompleteEvent { user, reliability_score }.publish(&env);
 
-        env.events().publish(("verification_complete", user), reliability_score);
+        Ok(())
     }
 
     // Start monitoring loan for automatic repayments
-    pub fn start_monitoring_loan(env: Env, loan_id: u64) {
+    pub fn start_monitoring_loan(env: Env, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
         let loan_manager: Address = env
             .storage()
             .instance()
             .get(&DataKey::LoanManagerContract)
-            .unwrap();
+            .ok_or(Error::NotInitialized)?;
         loan_manager.require_auth();
 
-        env.storage().instance().set(&DataKey::MonitoredLoans(loan_id), &true);
+        storage::write(&env, &DataKey::MonitoredLoans(loan_id), &true);
+
+        MonitoringStartedEvent { loan_id }.publish(&env);
 
-        env.events().publish(("monitoring_started",), loan_id);
+        Ok(())
     }
 
-    // Oracle detects remittance and triggers automatic repayment
+    // Oracle detects remittance and triggers automatic repayment.
+    // Returns the part of the remittance left over for the recipient.
     pub fn report_remittance(
         env: Env,
         operator: Address,
@@ -158,18 +488,18 @@ impl OracleVerifier {
         nft_id: u64,
         amount: i128,
         loan_id: u64
-    ) {
-        Self::verify_operator(&env, &operator);
-        operator.require_auth();
+    ) -> Result<i128, Error> {
+        storage::extend_instance(&env);
 
-        // Check if loan is being monitored
-        let is_monitored: bool = env
-            .storage()
-            .instance()
-            .get(&DataKey::MonitoredLoans(loan_id))
-            .unwrap_or(false);
+        Self::verify_operator(&env, &operator)?;
+        Self::require_not_paused(&env, PauseScope::Reports)?;
+        operator.require_auth();
 
-        assert!(is_monitored, "Loan not being monitored");
+        // Make sure the report matches the loan being repaid
+        let loan = Self::reported_loan(&env, loan_id, nft_id)?;
+        if loan.borrower != user {
+            return Err(Error::BorrowerMismatch);
+        }
 
         // Update NFT with new remittance
         let nft_contract: Address = env
@@ -179,7 +509,8 @@ impl OracleVerifier {
             .unwrap();
         let nft_client = remittance::Client::new(&env, &nft_contract);
 
-        nft_client.update_remittance_data(&nft_id, &amount, &0_i128);
+        let nft_data = nft_client.get_nft_data(&nft_id);
+        nft_client.update_remittance_data(&nft_id, &amount, &(nft_data.total_sent + amount));
 
         // Process automatic repayment through LoanManager
         let loan_manager: Address = env
@@ -188,18 +519,29 @@ impl OracleVerifier {
             .get(&DataKey::LoanManagerContract)
             .unwrap();
         let loan_manager_client = loan_manager::Client::new(&env, &loan_manager);
+        let remaining = loan_manager_client.process_automatic_repayment(&loan_id, &amount);
 
-        loan_manager_client.process_automatic_repayment(&loan_id, &amount);
-        // let remaining = loan_manager.process_automatic_repayment(loan_id, amount)
+        RemittanceReportedEvent { loan_id, nft_id, amount, remaining }.publish(&env);
 
-        env.events().publish(("remittance_reported", loan_id, nft_id), amount);
+        Ok(remaining)
     }
 
     // Oracle reports missed payment
-    pub fn report_missed_payment(env: Env, operator: Address, loan_id: u64, nft_id: u64) {
-        Self::verify_operator(&env, &operator);
+    pub fn report_missed_payment(
+        env: Env,
+        operator: Address,
+        loan_id: u64,
+        nft_id: u64
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::verify_operator(&env, &operator)?;
+        Self::require_not_paused(&env, PauseScope::Reports)?;
         operator.require_auth();
 
+        // A miss against the wrong loan or NFT would penalize an unrelated borrower
+        Self::reported_loan(&env, loan_id, nft_id)?;
+
         // Update NFT
         let nft_contract: Address = env
             .storage()
@@ -221,42 +563,184 @@ impl OracleVerifier {
 
         loan_manager_client.mark_payment_missed(&loan_id);
 
-        env.events().publish(("payment_missed_reported", loan_id), nft_id);
+        PaymentMissedReportedEvent { loan_id, nft_id }.publish(&env);
+
+        Ok(())
     }
 
     // Get verification status
-    pub fn get_verification_status(env: Env, user: Address) -> VerificationStatus {
-        let request: VerificationRequest = env
+    pub fn get_verification_status(env: Env, user: Address) -> Result<VerificationStatus, Error> {
+        storage::extend_instance(&env);
+
+        let request: VerificationRequest = storage::read(&env, &DataKey::VerificationRequest(user))
+            .ok_or(Error::VerificationNotFound)?;
+
+        Ok(request.status)
+    }
+
+    // Operator manager authorizes a new oracle operator; adding an existing one does nothing
+    pub fn add_operator(env: Env, caller: Address, operator: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::OperatorManager, &caller)?;
+
+        if Self::operator_index(&env, &operator)?.is_some() {
+            return Ok(());
+        }
+
+        let count = Self::operator_count(&env)?;
+        env.storage().instance().set(&DataKey::OracleOperators(count), &operator);
+        env.storage().instance().set(&DataKey::OracleOperatorCount, &(count + 1));
+
+        OperatorAddedEvent { operator }.publish(&env);
+        Ok(())
+    }
+
+    // Operator manager withdraws an operator's authorization
+    pub fn remove_operator(env: Env, caller: Address, operator: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::OperatorManager, &caller)?;
+
+        let index = Self::operator_index(&env, &operator)?.ok_or(Error::UnauthorizedOperator)?;
+
+        // Move the last operator into the freed slot
+        let last = Self::operator_count(&env)? - 1;
+        if index != last {
+            let moved: Address = env.storage().instance().get(&DataKey::OracleOperators(last)).unwrap();
+            env.storage().instance().set(&DataKey::OracleOperators(index), &moved);
+        }
+        env.storage().instance().remove(&DataKey::OracleOperators(last));
+        env.storage().instance().set(&DataKey::OracleOperatorCount, &last);
+
+        OperatorRemovedEvent { operator }.publish(&env);
+        Ok(())
+    }
+
+    pub fn get_operators(env: Env) -> Result<Vec<Address>, Error> {
+        storage::extend_instance(&env);
+
+        let mut operators = Vec::new(&env);
+        for i in 0..Self::operator_count(&env)? {
+            operators.push_back(env.storage().instance().get(&DataKey::OracleOperators(i)).unwrap());
+        }
+        Ok(operators)
+    }
+
+    // Anyone can extend the TTL of persistent entries, e.g. a keeper keeping live data
+    // from being archived. Returns how many of `keys` exist.
+    pub fn bump(env: Env, keys: Vec<DataKey>) -> u32 {
+        storage::extend_instance(&env);
+
+        let mut extended = 0;
+        for key in keys.iter() {
+            if storage::extend(&env, &key) {
+                extended += 1;
+            }
+        }
+        extended
+    }
+
+    // Internal: Give a contract deployed from the original code, which had no admin, the
+    // admin of the LoanManager it was wired to
+    fn adopt_legacy_admin(env: &Env) {
+        if env.storage().instance().has(&DataKey::SchemaVersion) || access::admin(env).is_some() {
+            return;
+        }
+        let loan_manager: Option<Address> = env.storage().instance().get(&DataKey::LoanManagerContract);
+        let Some(loan_manager) = loan_manager else {
+            return;
+        };
+
+        if let Some(admin) = loan_manager::Client::new(env, &loan_manager).get_admin() {
+            access::set_admin(env, &admin);
+        }
+    }
+
+    // Internal: Move the monitoring flags of the next MIGRATION_BATCH loans out of instance
+    // storage. Returns true once every loan has been checked.
+    fn migrate_to_v1(env: &Env) -> bool {
+        let loan_manager: Address = env
             .storage()
             .instance()
-            .get(&DataKey::VerificationRequest(user))
-            .expect("No verification request found");
+            .get(&DataKey::LoanManagerContract)
+            .unwrap();
+        let counter = loan_manager::Client::new(env, &loan_manager).get_loan_counter();
+        let cursor: u64 = env.storage().instance().get(&DataKey::MigrationCursor).unwrap_or(0);
+        let end = counter.min(cursor + MIGRATION_BATCH);
 
-        request.status
+        for loan_id in (cursor + 1)..=end {
+            storage::migrate(env, &DataKey::MonitoredLoans(loan_id));
+        }
+
+        if end < counter {
+            env.storage().instance().set(&DataKey::MigrationCursor, &end);
+            return false;
+        }
+        env.storage().instance().remove(&DataKey::MigrationCursor);
+        true
+    }
+
+    // Internal: Fail while `scope` is paused
+    fn require_not_paused(env: &Env, scope: PauseScope) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Paused(scope)) {
+            return Err(Error::Paused);
+        }
+        Ok(())
     }
 
     // Internal: Verify operator is authorized
-    fn verify_operator(env: &Env, operator: &Address) {
-        let count: u32 = env.storage().instance().get(&DataKey::OracleOperatorCount).unwrap();
+    fn verify_operator(env: &Env, operator: &Address) -> Result<(), Error> {
+        Self::operator_index(env, operator)?.ok_or(Error::UnauthorizedOperator)?;
+        Ok(())
+    }
 
-        for i in 0..count {
+    // Internal: Number of authorized operators
+    fn operator_count(env: &Env) -> Result<u32, Error> {
+        env.storage().instance().get(&DataKey::OracleOperatorCount).ok_or(Error::NotInitialized)
+    }
+
+    // Internal: Slot holding `operator` in the operator list, if it is authorized
+    fn operator_index(env: &Env, operator: &Address) -> Result<Option<u32>, Error> {
+        for i in 0..Self::operator_count(env)? {
             let authorized: Address = env
                 .storage()
                 .instance()
                 .get(&DataKey::OracleOperators(i))
                 .unwrap();
             if &authorized == operator {
-                return;
+                return Ok(Some(i));
             }
         }
 
-        panic!("Unauthorized operator");
+        Ok(None)
+    }
+
+    // Internal: The monitored loan a report is about, once `nft_id` is confirmed as its collateral
+    fn reported_loan(env: &Env, loan_id: u64, nft_id: u64) -> Result<loan_manager::Loan, Error> {
+        let is_monitored: bool = storage::read(env, &DataKey::MonitoredLoans(loan_id))
+            .unwrap_or(false);
+
+        if !is_monitored {
+            return Err(Error::LoanNotMonitored);
+        }
+
+        let loan_manager: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LoanManagerContract)
+            .unwrap();
+        let loan = loan_manager::Client::new(env, &loan_manager).get_loan(&loan_id);
+        if loan.nft_collateral_id != nft_id {
+            return Err(Error::CollateralMismatch);
+        }
+        Ok(loan)
     }
 
     // Internal: Calculate reliability score from payment history
     fn calculate_reliability_score(payment_history: &Vec<remittance::PaymentRecord>) -> u32 {
         let mut paid_count = 0u32;
-        let total_count = payment_history.len() as u32;
+        let total_count = payment_history.len();
 
         if total_count == 0 {
             return 100;
@@ -271,3 +755,6 @@ impl OracleVerifier {
         (paid_count * 100) / total_count
     }
 }
+
+#[cfg(test)]
+mod test;
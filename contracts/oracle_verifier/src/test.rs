@@ -0,0 +1,308 @@
+use super::*;
+use soroban_sdk::{
+    testutils::{Address as _, MockAuth, MockAuthInvoke},
+    token::{StellarAssetClient, TokenClient},
+    IntoVal,
+};
+
+mod pool {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/lending_pool.wasm"
+    );
+}
+
+struct Setup<'a> {
+    env: Env,
+    oracle: OracleVerifierClient<'a>,
+    loan_manager: loan_manager::Client<'a>,
+    nft: remittance::Client<'a>,
+    token: TokenClient<'a>,
+    admin: Address,
+    operator: Address,
+    borrower: Address,
+    pool_address: Address,
+}
+
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let admin = Address::generate(&env);
+    let operator = Address::generate(&env);
+    let lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let token = TokenClient::new(&env, &usdc.address());
+    let token_admin = StellarAssetClient::new(&env, &usdc.address());
+    token_admin.mint(&lender, &100_000);
+    token_admin.mint(&borrower, &10_000);
+
+    let nft_id = env.register(remittance::WASM, ());
+    let pool_id = env.register(pool::WASM, ());
+    let loan_manager_id = env.register(loan_manager::WASM, ());
+    let oracle_id = env.register(OracleVerifier, ());
+
+    let nft = remittance::Client::new(&env, &nft_id);
+    let pool = pool::Client::new(&env, &pool_id);
+    let loan_manager = loan_manager::Client::new(&env, &loan_manager_id);
+    let oracle = OracleVerifierClient::new(&env, &oracle_id);
+
+    nft.initialize(&admin, &oracle_id, &loan_manager_id);
+    pool.initialize(&admin, &loan_manager_id, &usdc.address(), &500);
+    loan_manager.initialize(&admin, &nft_id, &pool_id, &oracle_id, &usdc.address());
+    oracle.initialize(
+        &admin,
+        &nft_id,
+        &loan_manager_id,
+        &Vec::from_array(&env, [operator.clone()]),
+    );
+
+    pool.deposit(&lender, &50_000);
+
+    // Verify the borrower: 11 of 12 months paid gives a score of 91,
+    // so 2_000 a month backs 7_644 of collateral over 6 months
+    let mut history = Vec::new(&env);
+    for month_index in 1..=12u32 {
+        history.push_back(remittance::PaymentRecord { month_index, paid: month_index != 6 });
+    }
+    oracle.request_verification(
+        &borrower,
+        &String::from_str(&env, "wise"),
+        &String::from_str(&env, "acct-1"),
+    );
+    oracle.submit_verification(&operator, &borrower, &2_000, &12, &24_000, &history);
+
+    // 6_000 over 6 months at 5.6% APR (12% utilization, top risk tier): 1_017 per month
+    let loan_id = loan_manager.request_loan(&borrower, &1, &6_000, &6);
+    loan_manager.approve_loan(&loan_id);
+
+    Setup { env, oracle, loan_manager, nft, token, admin, operator, borrower, pool_address: pool_id }
+}
+
+#[test]
+fn test_report_remittance_repays_from_allowance() {
+    let s = setup();
+
+    let expiration = s.env.ledger().sequence() + 1_000;
+    s.token.approve(&s.borrower, &s.loan_manager.address, &5_000, &expiration);
+
+    let remaining = s.oracle.report_remittance(&s.operator, &s.borrower, &1, &1_500, &1);
+    assert_eq!(remaining, 483);
+
+    let loan = s.loan_manager.get_loan(&1);
+    assert_eq!(loan.payments_made, 1);
+    assert_eq!(loan.total_repaid, 1_017);
+    assert_eq!(loan.outstanding_balance, 6_000 - 990);
+
+    assert_eq!(s.token.balance(&s.borrower), 10_000 + 6_000 - 1_017);
+    assert_eq!(s.token.balance(&s.pool_address), 50_000 - 6_000 + 1_017);
+    assert_eq!(s.token.allowance(&s.borrower, &s.loan_manager.address), 5_000 - 1_017);
+
+    let nft_data = s.nft.get_nft_data(&1);
+    assert_eq!(nft_data.total_sent, 25_500);
+    assert_eq!(nft_data.monthly_amount, 1_500);
+}
+
+#[test]
+fn test_report_remittance_without_allowance_fails() {
+    let s = setup();
+
+    assert!(s.oracle.try_report_remittance(&s.operator, &s.borrower, &1, &1_500, &1).is_err());
+    assert_eq!(s.loan_manager.get_loan(&1).payments_made, 0);
+}
+
+#[test]
+fn test_automatic_repayment_requires_oracle() {
+    let s = setup();
+    let attacker = Address::generate(&s.env);
+
+    let expiration = s.env.ledger().sequence() + 1_000;
+    s.token.approve(&s.borrower, &s.loan_manager.address, &5_000, &expiration);
+
+    s.env.mock_auths(&[MockAuth {
+        address: &attacker,
+        invoke: &MockAuthInvoke {
+            contract: &s.loan_manager.address,
+            fn_name: "process_automatic_repayment",
+            args: (1u64, 1_500i128).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.loan_manager.try_process_automatic_repayment(&1, &1_500).is_err());
+
+    assert_eq!(s.loan_manager.get_loan(&1).payments_made, 0);
+    assert_eq!(s.token.allowance(&s.borrower, &s.loan_manager.address), 5_000);
+}
+
+#[test]
+fn test_initialize_only_once() {
+    let s = setup();
+    let attacker = Address::generate(&s.env);
+
+    let operators = Vec::from_array(&s.env, [attacker.clone()]);
+    assert_eq!(
+        s.oracle.try_initialize(&attacker, &attacker, &attacker, &operators),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+
+    // The original operator and loan manager are still in place
+    let expiration = s.env.ledger().sequence() + 1_000;
+    s.token.approve(&s.borrower, &s.loan_manager.address, &5_000, &expiration);
+    s.oracle.report_remittance(&s.operator, &s.borrower, &1, &1_500, &1);
+    assert_eq!(s.loan_manager.get_loan(&1).payments_made, 1);
+}
+
+#[test]
+fn test_oracle_errors() {
+    let s = setup();
+    let stranger = Address::generate(&s.env);
+
+    assert_eq!(
+        s.oracle.try_report_remittance(&stranger, &s.borrower, &1, &1_500, &1),
+        Err(Ok(Error::UnauthorizedOperator))
+    );
+    assert_eq!(
+        s.oracle.try_report_remittance(&s.operator, &s.borrower, &1, &1_500, &2),
+        Err(Ok(Error::LoanNotMonitored))
+    );
+    assert_eq!(
+        s.oracle.try_report_remittance(&s.operator, &stranger, &1, &1_500, &1),
+        Err(Ok(Error::BorrowerMismatch))
+    );
+    assert_eq!(
+        s.oracle.try_submit_verification(&s.operator, &s.borrower, &2_000, &12, &24_000, &Vec::new(&s.env)),
+        Err(Ok(Error::AlreadyProcessed))
+    );
+    assert_eq!(s.oracle.try_get_verification_status(&stranger), Err(Ok(Error::VerificationNotFound)));
+}
+
+#[test]
+fn test_verification_mints_a_verified_nft() {
+    let s = setup();
+
+    assert!(s.nft.get_nft_data(&1).verified);
+    let self_minted = s.nft.mint(&s.borrower, &2_000, &100, &12, &24_000, &Vec::new(&s.env));
+    assert!(!s.nft.get_nft_data(&self_minted).verified);
+}
+
+#[test]
+fn test_report_missed_payment_checks_loan_and_nft() {
+    let s = setup();
+    let other_nft = s.nft.mint(&s.borrower, &2_000, &91, &12, &24_000, &Vec::new(&s.env));
+
+    assert_eq!(
+        s.oracle.try_report_missed_payment(&s.operator, &2, &1),
+        Err(Ok(Error::LoanNotMonitored))
+    );
+    assert_eq!(
+        s.oracle.try_report_missed_payment(&s.operator, &1, &other_nft),
+        Err(Ok(Error::CollateralMismatch))
+    );
+    assert_eq!(s.nft.get_nft_data(&other_nft).lifetime_missed_payments, 0);
+    assert_eq!(s.loan_manager.get_loan(&1).payments_missed, 0);
+
+    let missed_before = s.nft.get_nft_data(&1).lifetime_missed_payments;
+    s.oracle.report_missed_payment(&s.operator, &1, &1);
+    assert_eq!(s.nft.get_nft_data(&1).lifetime_missed_payments, missed_before + 1);
+    assert_eq!(s.loan_manager.get_loan(&1).payments_missed, 1);
+}
+
+#[test]
+fn test_migrate_moves_instance_storage_to_persistent() {
+    let s = setup();
+    let stranger = Address::generate(&s.env);
+
+    // The original contract kept the monitoring flag and the request in instance storage
+    s.env.as_contract(&s.oracle.address, || {
+        for key in [DataKey::MonitoredLoans(1), DataKey::VerificationRequest(s.borrower.clone())] {
+            let value: soroban_sdk::Val = s.env.storage().persistent().get(&key).unwrap();
+            s.env.storage().persistent().remove(&key);
+            s.env.storage().instance().set(&key, &value);
+        }
+        s.env.storage().instance().remove(&DataKey::SchemaVersion);
+    });
+    assert_eq!(s.oracle.schema_version(), 0);
+    assert_eq!(s.oracle.migrate(), 1);
+
+    let users = Vec::from_array(&s.env, [s.borrower.clone(), stranger]);
+    assert_eq!(s.oracle.migrate_verification_requests(&users), 1);
+    assert_eq!(s.oracle.migrate_verification_requests(&users), 0);
+
+    s.env.as_contract(&s.oracle.address, || {
+        for key in [DataKey::MonitoredLoans(1), DataKey::VerificationRequest(s.borrower.clone())] {
+            assert!(s.env.storage().persistent().has(&key));
+            assert!(!s.env.storage().instance().has(&key));
+        }
+    });
+
+    assert_eq!(s.oracle.get_verification_status(&s.borrower), VerificationStatus::Verified);
+    let expiration = s.env.ledger().sequence() + 1_000;
+    s.token.approve(&s.borrower, &s.loan_manager.address, &5_000, &expiration);
+    s.oracle.report_remittance(&s.operator, &s.borrower, &1, &1_500, &1);
+}
+
+#[test]
+fn test_migrate_adopts_loan_manager_admin_for_original_deployment() {
+    let s = setup();
+
+    // The original contract stored neither an admin nor a schema version
+    s.env.as_contract(&s.oracle.address, || {
+        access::renounce_admin(&s.env).unwrap();
+        s.env.storage().instance().remove(&DataKey::SchemaVersion);
+    });
+    assert_eq!(s.oracle.get_admin(), None);
+
+    assert_eq!(s.oracle.migrate(), 1);
+    assert_eq!(s.oracle.get_admin(), Some(s.admin.clone()));
+
+    // Only once: a later renounce is not undone
+    s.oracle.renounce_admin();
+    assert_eq!(s.oracle.try_migrate(), Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn test_operator_manager_adds_and_removes_operators() {
+    let s = setup();
+    let manager = Address::generate(&s.env);
+    let new_operator = Address::generate(&s.env);
+
+    assert_eq!(s.oracle.try_add_operator(&manager, &new_operator), Err(Ok(Error::Unauthorized)));
+
+    s.oracle.grant_role(&Role::OperatorManager, &manager);
+    s.oracle.add_operator(&manager, &new_operator);
+    s.oracle.add_operator(&manager, &new_operator);
+    assert_eq!(s.oracle.get_operators(), Vec::from_array(&s.env, [s.operator.clone(), new_operator.clone()]));
+
+    s.oracle.remove_operator(&manager, &s.operator);
+    assert_eq!(s.oracle.get_operators(), Vec::from_array(&s.env, [new_operator.clone()]));
+    assert_eq!(s.oracle.try_remove_operator(&manager, &s.operator), Err(Ok(Error::UnauthorizedOperator)));
+    assert_eq!(
+        s.oracle.try_report_remittance(&s.operator, &s.borrower, &1, &1_500, &1),
+        Err(Ok(Error::UnauthorizedOperator))
+    );
+
+    // The admin manages operators without holding the role
+    s.oracle.add_operator(&s.admin, &s.operator);
+    assert_eq!(s.oracle.get_operators().len(), 2);
+}
+
+#[test]
+fn test_pause_reports() {
+    let s = setup();
+
+    assert_eq!(s.oracle.try_pause(&s.operator, &PauseScope::Reports), Err(Ok(Error::Unauthorized)));
+
+    s.oracle.pause(&s.admin, &PauseScope::Reports);
+    assert!(s.oracle.is_paused(&PauseScope::Reports));
+    assert_eq!(
+        s.oracle.try_report_remittance(&s.operator, &s.borrower, &1, &1_500, &1),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(s.oracle.try_report_missed_payment(&s.operator, &1, &1), Err(Ok(Error::Paused)));
+
+    s.oracle.unpause(&s.admin, &PauseScope::Reports);
+    let expiration = s.env.ledger().sequence() + 1_000;
+    s.token.approve(&s.borrower, &s.loan_manager.address, &5_000, &expiration);
+    s.oracle.report_remittance(&s.operator, &s.borrower, &1, &1_500, &1);
+}
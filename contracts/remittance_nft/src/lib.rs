@@ -1,6 +1,28 @@
 #![no_std]
 
-use soroban_sdk::{ contract, contractimpl, contracttype, Address, Env, Vec };
+use soroban_sdk::{
+    contract,
+    contracterror,
+    contractevent,
+    contractimpl,
+    contracttype,
+    Address,
+    BytesN,
+    Env,
+    Symbol,
+    TryFromVal,
+    Val,
+    Vec,
+};
+
+use common::access::{ self, AccessError };
+use common::storage;
+
+// Storage layout version written by `initialize` and brought up to date by `migrate`
+const SCHEMA_VERSION: u32 = 1;
+
+// NFTs `migrate` moves per call
+const MIGRATION_BATCH: u64 = 50;
 
 #[contracttype]
 #[derive(Clone)]
@@ -14,6 +36,23 @@ pub struct RemittanceData {
     pub lifetime_missed_payments: u32,
     pub is_staked: bool,
     pub staked_in_loan: u64, // loan_id if staked
+    pub restructure_count: u32, // loans against this NFT that were restructured
+    pub verified: bool, // minted by the oracle from remittances it checked
+}
+
+// NFT data as the original contract stored it, before restructures were counted
+#[contracttype(export = false)]
+#[derive(Clone)]
+struct LegacyRemittanceData {
+    pub owner: Address,
+    pub monthly_amount: i128,
+    pub reliability_score: u32,
+    pub history_months: u32,
+    pub total_sent: i128,
+    pub last_remittance_timestamp: u64,
+    pub lifetime_missed_payments: u32,
+    pub is_staked: bool,
+    pub staked_in_loan: u64,
 }
 
 #[contracttype]
@@ -24,12 +63,112 @@ pub struct PaymentRecord {
 }
 
 #[contracttype]
+#[derive(Clone)]
 pub enum DataKey {
     TokenCounter,
     RemittanceData(u64), // token_id -> RemittanceData
     PaymentHistory(u64), // token_id -> Vec<PaymentRecord>
     OracleAddress,
     LoanManagerAddress,
+    SchemaVersion,
+    MigrationCursor, // last token id an unfinished migration step has moved
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    /// The contract has already been initialized
+    AlreadyInitialized = 1,
+    /// The contract has not been initialized yet
+    NotInitialized = 2,
+    /// No NFT exists with the given token id
+    NftNotFound = 3,
+    /// The NFT is already staked as loan collateral
+    NftAlreadyStaked = 4,
+    /// The NFT is not staked as loan collateral
+    NftNotStaked = 5,
+    /// The sender does not own the NFT
+    NotOwner = 6,
+    /// The caller is not the admin and does not hold the required role
+    Unauthorized = 7,
+    /// No admin transfer has been proposed
+    NoPendingAdmin = 8,
+}
+
+impl From<AccessError> for Error {
+    fn from(error: AccessError) -> Self {
+        match error {
+            AccessError::Unauthorized => Error::Unauthorized,
+            AccessError::NoPendingAdmin => Error::NoPendingAdmin,
+        }
+    }
+}
+
+#[contractevent(topics = ["mint_nft"], data_format = "single-value")]
+pub struct NftMintedEvent {
+    #[topic]
+    pub owner: Address,
+    pub token_id: u64,
+}
+
+#[contractevent(topics = ["stake_nft"], data_format = "single-value")]
+pub struct NftStakedEvent {
+    #[topic]
+    pub token_id: u64,
+    pub loan_id: u64,
+}
+
+#[contractevent(topics = ["unstake_nft"], data_format = "single-value")]
+pub struct NftUnstakedEvent {
+    pub token_id: u64,
+}
+
+#[contractevent(topics = ["seize_nft"], data_format = "single-value")]
+pub struct NftSeizedEvent {
+    #[topic]
+    pub token_id: u64,
+    pub recipient: Address,
+}
+
+#[contractevent(topics = ["transfer_nft"], data_format = "single-value")]
+pub struct NftTransferredEvent {
+    #[topic]
+    pub token_id: u64,
+    #[topic]
+    pub from: Address,
+    pub to: Address,
+}
+
+#[contractevent(topics = ["restructure_nft"], data_format = "single-value")]
+pub struct NftRestructuredEvent {
+    #[topic]
+    pub token_id: u64,
+    pub loan_id: u64,
+}
+
+#[contractevent(topics = ["update_nft"], data_format = "single-value")]
+pub struct NftUpdatedEvent {
+    #[topic]
+    pub token_id: u64,
+    pub reliability_score: u32,
+}
+
+#[contractevent(topics = ["payment_missed"], data_format = "single-value")]
+pub struct PaymentMissedEvent {
+    #[topic]
+    pub token_id: u64,
+    pub reliability_score: u32,
+}
+
+#[contractevent(topics = ["upgraded"], data_format = "single-value")]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+}
+
+#[contractevent(topics = ["migrated"], data_format = "single-value")]
+pub struct MigratedEvent {
+    pub schema_version: u32,
 }
 
 #[contract]
@@ -37,25 +176,110 @@ pub struct RemittanceNFT;
 
 #[contractimpl]
 impl RemittanceNFT {
-    pub fn __initialize(env: Env, oracle: Address, loan_manager: Address) {
-        env.storage().instance().set(&DataKey::OracleAddress, &oracle);
-        env.storage().instance().set(&DataKey::LoanManagerAddress, &loan_manager);
-        env.storage().instance().set(&DataKey::TokenCounter, &0u64);
-    }
-
     // Public initialize function that can be called after deployment
-    pub fn initialize(env: Env, admin: Address, oracle: Address, loan_manager: Address) {
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        oracle: Address,
+        loan_manager: Address
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
         admin.require_auth();
 
         // Only allow initialization if not already initialized
-        let existing: Option<Address> = env.storage().instance().get(&DataKey::OracleAddress);
-        assert!(existing.is_none(), "Contract already initialized");
+        if env.storage().instance().has(&DataKey::OracleAddress) {
+            return Err(Error::AlreadyInitialized);
+        }
 
         env.storage().instance().set(&DataKey::OracleAddress, &oracle);
         env.storage().instance().set(&DataKey::LoanManagerAddress, &loan_manager);
         env.storage().instance().set(&DataKey::TokenCounter, &0u64);
+        access::set_admin(&env, &admin);
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+
+        Ok(())
+    }
+
+    // Admin replaces the contract's code. Data is left as is; call `migrate` afterwards
+    // if the new code expects a newer storage layout.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        UpgradedEvent { wasm_hash: new_wasm_hash }.publish(&env);
+
+        Ok(())
+    }
+
+    // Storage layout version the contract's data is in; 0 for deployments that predate versioning
+    pub fn schema_version(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::SchemaVersion).unwrap_or(0)
+    }
+
+    // Admin brings stored data up to SCHEMA_VERSION. Large steps take several calls;
+    // returns the version the data is in afterwards. The original contract never stored
+    // its admin, so the first migration adopts the LoanManager's.
+    pub fn migrate(env: Env) -> Result<u32, Error> {
+        storage::extend_instance(&env);
+
+        Self::adopt_legacy_admin(&env);
+        access::require_admin(&env)?;
+
+        let mut version = Self::schema_version(env.clone());
+
+        // 0 -> 1: per-NFT entries move from instance to persistent storage
+        if version == 0 {
+            if !Self::migrate_to_v1(&env) {
+                return Ok(version);
+            }
+            version = 1;
+            env.storage().instance().set(&DataKey::SchemaVersion, &version);
+            MigratedEvent { schema_version: version }.publish(&env);
+        }
+
+        Ok(version)
+    }
+
+    pub fn get_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::admin(&env)
+    }
+
+    // Successor named by `propose_admin` who has not accepted yet
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::pending_admin(&env)
+    }
+
+    // Admin nominates a successor, who takes over by calling `accept_admin`
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::propose_admin(&env, &new_admin)?)
     }
 
+    // The nominated successor takes over as admin
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::accept_admin(&env)?)
+    }
+
+    // Admin gives up control for good; admin-only calls fail from then on
+    pub fn renounce_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::renounce_admin(&env)?)
+    }
+
+    // Owner mints an NFT from remittance data it reports itself
     pub fn mint(
         env: Env,
         owner: Address,
@@ -65,68 +289,179 @@ impl RemittanceNFT {
         total_sent: i128,
         payment_history: Vec<PaymentRecord>
     ) -> u64 {
-        owner.require_auth();
+        storage::extend_instance(&env);
 
-        // Get and increment token counter
-        let mut counter: u64 = env.storage().instance().get(&DataKey::TokenCounter).unwrap_or(0);
-        counter += 1;
+        owner.require_auth();
 
-        // Create remittance data
-        let data = RemittanceData {
-            owner: owner.clone(),
+        Self::mint_token(
+            &env,
+            owner,
             monthly_amount,
             reliability_score,
             history_months,
             total_sent,
-            last_remittance_timestamp: env.ledger().timestamp(),
-            lifetime_missed_payments: Self::count_missed_payments(&payment_history),
-            is_staked: false,
-            staked_in_loan: 0,
-        };
+            payment_history,
+            false
+        )
+    }
 
-        // Store data
-        env.storage().instance().set(&DataKey::TokenCounter, &counter);
-        env.storage().instance().set(&DataKey::RemittanceData(counter), &data);
-        env.storage().instance().set(&DataKey::PaymentHistory(counter), &payment_history);
+    // Mint an NFT from remittance data the oracle has checked (called by Oracle only)
+    pub fn mint_verified(
+        env: Env,
+        owner: Address,
+        monthly_amount: i128,
+        reliability_score: u32,
+        history_months: u32,
+        total_sent: i128,
+        payment_history: Vec<PaymentRecord>
+    ) -> Result<u64, Error> {
+        storage::extend_instance(&env);
 
-        // Emit event
-        env.events().publish(("mint_nft", owner), counter);
+        Self::require_oracle(&env)?;
 
-        counter
+        Ok(Self::mint_token(
+            &env,
+            owner,
+            monthly_amount,
+            reliability_score,
+            history_months,
+            total_sent,
+            payment_history,
+            true
+        ))
     }
 
     // Stake NFT as loan collateral (called by LoanManager only)
-    pub fn stake_nft(env: Env, token_id: u64, loan_id: u64) {
-        let mut data: RemittanceData = env
-            .storage()
-            .instance()
-            .get(&DataKey::RemittanceData(token_id))
-            .expect("NFT does not exist");
+    pub fn stake_nft(env: Env, token_id: u64, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_loan_manager(&env)?;
 
-        assert!(!data.is_staked, "NFT already staked");
+        let mut data: RemittanceData = Self::read_data(&env, token_id)
+            .ok_or(Error::NftNotFound)?;
+
+        if data.is_staked {
+            return Err(Error::NftAlreadyStaked);
+        }
 
         data.is_staked = true;
         data.staked_in_loan = loan_id;
 
-        env.storage().instance().set(&DataKey::RemittanceData(token_id), &data);
-        env.events().publish(("stake_nft", token_id), loan_id);
+        storage::write(&env, &DataKey::RemittanceData(token_id), &data);
+        NftStakedEvent { token_id, loan_id }.publish(&env);
+
+        Ok(())
     }
 
     // Unstake NFT after loan repayment (called by LoanManager only)
-    pub fn unstake_nft(env: Env, token_id: u64) {
-        let mut data: RemittanceData = env
-            .storage()
-            .instance()
-            .get(&DataKey::RemittanceData(token_id))
-            .expect("NFT does not exist");
+    pub fn unstake_nft(env: Env, token_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_loan_manager(&env)?;
 
-        assert!(data.is_staked, "NFT not staked");
+        let mut data: RemittanceData = Self::read_data(&env, token_id)
+            .ok_or(Error::NftNotFound)?;
+
+        if !data.is_staked {
+            return Err(Error::NftNotStaked);
+        }
 
         data.is_staked = false;
         data.staked_in_loan = 0;
 
-        env.storage().instance().set(&DataKey::RemittanceData(token_id), &data);
-        env.events().publish(("unstake_nft",), token_id);
+        storage::write(&env, &DataKey::RemittanceData(token_id), &data);
+        NftUnstakedEvent { token_id }.publish(&env);
+
+        Ok(())
+    }
+
+    // Move a staked NFT onto the loan that refinanced its current one (called by LoanManager only)
+    pub fn restake_nft(env: Env, token_id: u64, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_loan_manager(&env)?;
+
+        let mut data: RemittanceData = Self::read_data(&env, token_id)
+            .ok_or(Error::NftNotFound)?;
+
+        if !data.is_staked {
+            return Err(Error::NftNotStaked);
+        }
+
+        data.staked_in_loan = loan_id;
+
+        storage::write(&env, &DataKey::RemittanceData(token_id), &data);
+        NftStakedEvent { token_id, loan_id }.publish(&env);
+
+        Ok(())
+    }
+
+    // Owner transfers an unstaked NFT
+    pub fn transfer(env: Env, from: Address, to: Address, token_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        from.require_auth();
+
+        let mut data: RemittanceData = Self::read_data(&env, token_id)
+            .ok_or(Error::NftNotFound)?;
+
+        if data.owner != from {
+            return Err(Error::NotOwner);
+        }
+        if data.is_staked {
+            return Err(Error::NftAlreadyStaked);
+        }
+
+        data.owner = to.clone();
+
+        storage::write(&env, &DataKey::RemittanceData(token_id), &data);
+        NftTransferredEvent { token_id, from, to }.publish(&env);
+
+        Ok(())
+    }
+
+    // Forfeit a staked NFT to `recipient` when its loan defaults (called by LoanManager only)
+    pub fn seize_nft(env: Env, token_id: u64, recipient: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_loan_manager(&env)?;
+
+        let mut data: RemittanceData = Self::read_data(&env, token_id)
+            .ok_or(Error::NftNotFound)?;
+
+        if !data.is_staked {
+            return Err(Error::NftNotStaked);
+        }
+
+        data.owner = recipient.clone();
+        data.is_staked = false;
+        data.staked_in_loan = 0;
+
+        storage::write(&env, &DataKey::RemittanceData(token_id), &data);
+        NftSeizedEvent { token_id, recipient }.publish(&env);
+
+        Ok(())
+    }
+
+    // Tag a staked NFT whose loan was restructured (called by LoanManager only)
+    pub fn record_restructure(env: Env, token_id: u64, loan_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Self::require_loan_manager(&env)?;
+
+        let mut data: RemittanceData = Self::read_data(&env, token_id)
+            .ok_or(Error::NftNotFound)?;
+
+        if !data.is_staked {
+            return Err(Error::NftNotStaked);
+        }
+
+        data.restructure_count += 1;
+
+        storage::write(&env, &DataKey::RemittanceData(token_id), &data);
+        NftRestructuredEvent { token_id, loan_id }.publish(&env);
+
+        Ok(())
     }
 
     // Update remittance data (called by Oracle only)
@@ -135,20 +470,15 @@ impl RemittanceNFT {
         token_id: u64,
         new_monthly_amount: i128,
         new_total_sent: i128
-    ) {
-        let oracle: Address = env.storage().instance().get(&DataKey::OracleAddress).unwrap();
-        oracle.require_auth();
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
 
-        let mut data: RemittanceData = env
-            .storage()
-            .instance()
-            .get(&DataKey::RemittanceData(token_id))
-            .expect("NFT does not exist");
+        Self::require_oracle(&env)?;
 
-        let mut payment_history: Vec<PaymentRecord> = env
-            .storage()
-            .instance()
-            .get(&DataKey::PaymentHistory(token_id))
+        let mut data: RemittanceData = Self::read_data(&env, token_id)
+            .ok_or(Error::NftNotFound)?;
+
+        let mut payment_history: Vec<PaymentRecord> = storage::read(&env, &DataKey::PaymentHistory(token_id))
             .unwrap();
 
         // Add new payment to history
@@ -168,32 +498,28 @@ impl RemittanceNFT {
         data.history_months += 1;
         data.last_remittance_timestamp = env.ledger().timestamp();
         data.reliability_score = Self::calculate_score(
-            &env,
             &payment_history,
             data.lifetime_missed_payments
         );
 
-        env.storage().instance().set(&DataKey::RemittanceData(token_id), &data);
-        env.storage().instance().set(&DataKey::PaymentHistory(token_id), &payment_history);
+        storage::write(&env, &DataKey::RemittanceData(token_id), &data);
+        storage::write(&env, &DataKey::PaymentHistory(token_id), &payment_history);
+
+        NftUpdatedEvent { token_id, reliability_score: data.reliability_score }.publish(&env);
 
-        env.events().publish(("update_nft", token_id), data.reliability_score);
+        Ok(())
     }
 
     // Mark payment as missed (called by Oracle only)
-    pub fn mark_payment_missed(env: Env, token_id: u64) {
-        let oracle: Address = env.storage().instance().get(&DataKey::OracleAddress).unwrap();
-        oracle.require_auth();
+    pub fn mark_payment_missed(env: Env, token_id: u64) -> Result<(), Error> {
+        storage::extend_instance(&env);
 
-        let mut data: RemittanceData = env
-            .storage()
-            .instance()
-            .get(&DataKey::RemittanceData(token_id))
-            .expect("NFT does not exist");
+        Self::require_oracle(&env)?;
 
-        let mut payment_history: Vec<PaymentRecord> = env
-            .storage()
-            .instance()
-            .get(&DataKey::PaymentHistory(token_id))
+        let mut data: RemittanceData = Self::read_data(&env, token_id)
+            .ok_or(Error::NftNotFound)?;
+
+        let mut payment_history: Vec<PaymentRecord> = storage::read(&env, &DataKey::PaymentHistory(token_id))
             .unwrap();
 
         // Add missed payment
@@ -210,53 +536,206 @@ impl RemittanceNFT {
         data.history_months += 1;
         data.lifetime_missed_payments += 1;
         data.reliability_score = Self::calculate_score(
-            &env,
             &payment_history,
             data.lifetime_missed_payments
         );
 
-        env.storage().instance().set(&DataKey::RemittanceData(token_id), &data);
-        env.storage().instance().set(&DataKey::PaymentHistory(token_id), &payment_history);
+        storage::write(&env, &DataKey::RemittanceData(token_id), &data);
+        storage::write(&env, &DataKey::PaymentHistory(token_id), &payment_history);
+
+        PaymentMissedEvent { token_id, reliability_score: data.reliability_score }.publish(&env);
 
-        env.events().publish(("payment_missed", token_id), data.reliability_score);
+        Ok(())
     }
 
     // Get NFT data (public view)
-    pub fn get_nft_data(env: Env, token_id: u64) -> RemittanceData {
-        env.storage()
-            .instance()
-            .get(&DataKey::RemittanceData(token_id))
-            .expect("NFT does not exist")
+    pub fn get_nft_data(env: Env, token_id: u64) -> Result<RemittanceData, Error> {
+        storage::extend_instance(&env);
+
+        Self::read_data(&env, token_id)
+            .ok_or(Error::NftNotFound)
     }
 
     // Calculate collateral value
-    pub fn calculate_collateral_value(env: Env, token_id: u64, duration_months: u32) -> i128 {
-        let data: RemittanceData = Self::get_nft_data(env, token_id);
+    pub fn calculate_collateral_value(
+        env: Env,
+        token_id: u64,
+        duration_months: u32
+    ) -> Result<i128, Error> {
+        storage::extend_instance(&env);
+
+        let data: RemittanceData = Self::get_nft_data(env, token_id)?;
 
         // Formula: monthly_amount × duration × (score/100) × 0.70
         let base_value = data.monthly_amount * (duration_months as i128);
         let score_adjusted = (base_value * (data.reliability_score as i128)) / 100;
-        let final_value = (score_adjusted * 70) / 100;
-
-        final_value
+        Ok((score_adjusted * 70) / 100)
     }
 
     pub fn get_token_counter(env: Env) -> u64 {
+        storage::extend_instance(&env);
+
         env.storage()
             .instance()
             .get(&DataKey::TokenCounter)
             .unwrap_or(0)
     }
 
+    // Anyone can extend the TTL of persistent entries, e.g. a keeper keeping live data
+    // from being archived. Returns how many of `keys` exist.
+    pub fn bump(env: Env, keys: Vec<DataKey>) -> u32 {
+        storage::extend_instance(&env);
+
+        let mut extended = 0;
+        for key in keys.iter() {
+            if storage::extend(&env, &key) {
+                extended += 1;
+            }
+        }
+        extended
+    }
+
+    // Internal: Move the next MIGRATION_BATCH NFTs' data and payment history out of
+    // instance storage. Returns true once every NFT is moved.
+    fn migrate_to_v1(env: &Env) -> bool {
+        let counter: u64 = env.storage().instance().get(&DataKey::TokenCounter).unwrap_or(0);
+        let cursor: u64 = env.storage().instance().get(&DataKey::MigrationCursor).unwrap_or(0);
+        let end = counter.min(cursor + MIGRATION_BATCH);
+
+        for token_id in (cursor + 1)..=end {
+            storage::migrate(env, &DataKey::RemittanceData(token_id));
+            storage::migrate(env, &DataKey::PaymentHistory(token_id));
+            Self::read_data(env, token_id);
+        }
+
+        if end < counter {
+            env.storage().instance().set(&DataKey::MigrationCursor, &end);
+            return false;
+        }
+        env.storage().instance().remove(&DataKey::MigrationCursor);
+        true
+    }
+
+    // Internal: Give a contract deployed from the original code, which took an admin in
+    // `initialize` but never stored it, the admin of the LoanManager it was wired to
+    fn adopt_legacy_admin(env: &Env) {
+        if env.storage().instance().has(&DataKey::SchemaVersion) || access::admin(env).is_some() {
+            return;
+        }
+        let loan_manager: Option<Address> = env.storage().instance().get(&DataKey::LoanManagerAddress);
+        let Some(loan_manager) = loan_manager else {
+            return;
+        };
+
+        // LoanManager builds against this contract, so it is called without a generated client
+        let admin: Option<Address> = env.invoke_contract(
+            &loan_manager,
+            &Symbol::new(env, "get_admin"),
+            Vec::new(env)
+        );
+        if let Some(admin) = admin {
+            access::set_admin(env, &admin);
+        }
+    }
+
+    // Internal: The NFT's data, converting an entry the original contract wrote
+    fn read_data(env: &Env, token_id: u64) -> Option<RemittanceData> {
+        let key = DataKey::RemittanceData(token_id);
+        let value: Val = storage::read(env, &key)?;
+        if storage::has_field(env, &value, "restructure_count") {
+            return RemittanceData::try_from_val(env, &value).ok();
+        }
+        let legacy = LegacyRemittanceData::try_from_val(env, &value).ok()?;
+
+        let data = RemittanceData {
+            owner: legacy.owner,
+            monthly_amount: legacy.monthly_amount,
+            reliability_score: legacy.reliability_score,
+            history_months: legacy.history_months,
+            total_sent: legacy.total_sent,
+            last_remittance_timestamp: legacy.last_remittance_timestamp,
+            lifetime_missed_payments: legacy.lifetime_missed_payments,
+            is_staked: legacy.is_staked,
+            staked_in_loan: legacy.staked_in_loan,
+            restructure_count: 0,
+            verified: false,
+        };
+        storage::write(env, &key, &data);
+        Some(data)
+    }
+
+    // Internal: Store a new NFT and return its token id
+    #[allow(clippy::too_many_arguments)]
+    fn mint_token(
+        env: &Env,
+        owner: Address,
+        monthly_amount: i128,
+        reliability_score: u32,
+        history_months: u32,
+        total_sent: i128,
+        payment_history: Vec<PaymentRecord>,
+        verified: bool
+    ) -> u64 {
+        // Get and increment token counter
+        let mut counter: u64 = env.storage().instance().get(&DataKey::TokenCounter).unwrap_or(0);
+        counter += 1;
+
+        // Create remittance data
+        let data = RemittanceData {
+            owner: owner.clone(),
+            monthly_amount,
+            reliability_score,
+            history_months,
+            total_sent,
+            last_remittance_timestamp: env.ledger().timestamp(),
+            lifetime_missed_payments: Self::count_missed_payments(&payment_history),
+            is_staked: false,
+            staked_in_loan: 0,
+            restructure_count: 0,
+            verified,
+        };
+
+        // Store data
+        env.storage().instance().set(&DataKey::TokenCounter, &counter);
+        storage::write(env, &DataKey::RemittanceData(counter), &data);
+        storage::write(env, &DataKey::PaymentHistory(counter), &payment_history);
+
+        // Emit event
+        NftMintedEvent { owner, token_id: counter }.publish(env);
+
+        counter
+    }
+
+    // Internal: Require auth from the configured LoanManager contract
+    fn require_loan_manager(env: &Env) -> Result<(), Error> {
+        let loan_manager: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::LoanManagerAddress)
+            .ok_or(Error::NotInitialized)?;
+        loan_manager.require_auth();
+        Ok(())
+    }
+
+    // Internal: Require auth from the configured Oracle contract
+    fn require_oracle(env: &Env) -> Result<(), Error> {
+        let oracle: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::OracleAddress)
+            .ok_or(Error::NotInitialized)?;
+        oracle.require_auth();
+        Ok(())
+    }
+
     // Internal: Calculate reliability score
     fn calculate_score(
-        env: &Env,
         payment_history: &Vec<PaymentRecord>,
         lifetime_missed: u32
     ) -> u32 {
         // Count payments in last 24 months
         let mut paid_count = 0u32;
-        let total_count = payment_history.len() as u32;
+        let total_count = payment_history.len();
 
         for i in 0..payment_history.len() {
             if payment_history.get(i).unwrap().paid {
@@ -270,11 +749,7 @@ impl RemittanceNFT {
         // Apply lifetime penalty
         let penalty = Self::calculate_lifetime_penalty(lifetime_missed);
 
-        if recent_score > penalty {
-            recent_score - penalty
-        } else {
-            0
-        }
+        recent_score.saturating_sub(penalty)
     }
 
     // Internal: Calculate lifetime penalty
@@ -299,3 +774,6 @@ impl RemittanceNFT {
         count
     }
 }
+
+#[cfg(test)]
+mod test;
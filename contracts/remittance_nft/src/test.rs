@@ -0,0 +1,304 @@
+use super::*;
+use soroban_sdk::{ testutils::{ storage::Persistent as _, Address as _, Ledger, MockAuth, MockAuthInvoke }, IntoVal };
+
+struct Setup<'a> {
+    env: Env,
+    client: RemittanceNFTClient<'a>,
+    loan_manager: Address,
+    borrower: Address,
+    token_id: u64,
+}
+
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    let contract_id = env.register(RemittanceNFT, ());
+    let client = RemittanceNFTClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let loan_manager = Address::generate(&env);
+    let borrower = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.initialize(&admin, &oracle, &loan_manager);
+    let token_id = client.mint(&borrower, &500_0000000, &95, &12, &6000_0000000, &Vec::new(&env));
+
+    Setup { env, client, loan_manager, borrower, token_id }
+}
+
+fn mock_stake_auth(s: &Setup, signer: &Address, loan_id: u64) {
+    s.env.mock_auths(
+        &[
+            MockAuth {
+                address: signer,
+                invoke: &(MockAuthInvoke {
+                    contract: &s.client.address,
+                    fn_name: "stake_nft",
+                    args: (s.token_id, loan_id).into_val(&s.env),
+                    sub_invokes: &[],
+                }),
+            },
+        ]
+    );
+}
+
+fn mock_unstake_auth(s: &Setup, signer: &Address) {
+    s.env.mock_auths(
+        &[
+            MockAuth {
+                address: signer,
+                invoke: &(MockAuthInvoke {
+                    contract: &s.client.address,
+                    fn_name: "unstake_nft",
+                    args: (s.token_id,).into_val(&s.env),
+                    sub_invokes: &[],
+                }),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_loan_manager_can_stake_and_unstake() {
+    let s = setup();
+
+    mock_stake_auth(&s, &s.loan_manager, 7);
+    s.client.stake_nft(&s.token_id, &7);
+
+    let data = s.client.get_nft_data(&s.token_id);
+    assert!(data.is_staked);
+    assert_eq!(data.staked_in_loan, 7);
+
+    mock_unstake_auth(&s, &s.loan_manager);
+    s.client.unstake_nft(&s.token_id);
+
+    let data = s.client.get_nft_data(&s.token_id);
+    assert!(!data.is_staked);
+    assert_eq!(data.staked_in_loan, 0);
+}
+
+#[test]
+fn test_third_party_cannot_stake() {
+    let s = setup();
+    let attacker = Address::generate(&s.env);
+
+    mock_stake_auth(&s, &attacker, 7);
+    assert!(s.client.try_stake_nft(&s.token_id, &7).is_err());
+
+    assert!(!s.client.get_nft_data(&s.token_id).is_staked);
+}
+
+#[test]
+fn test_borrower_cannot_stake() {
+    let s = setup();
+
+    mock_stake_auth(&s, &s.borrower, 7);
+    assert!(s.client.try_stake_nft(&s.token_id, &7).is_err());
+
+    assert!(!s.client.get_nft_data(&s.token_id).is_staked);
+}
+
+#[test]
+fn test_third_party_cannot_unstake() {
+    let s = setup();
+    let attacker = Address::generate(&s.env);
+
+    mock_stake_auth(&s, &s.loan_manager, 7);
+    s.client.stake_nft(&s.token_id, &7);
+
+    mock_unstake_auth(&s, &attacker);
+    assert!(s.client.try_unstake_nft(&s.token_id).is_err());
+
+    let data = s.client.get_nft_data(&s.token_id);
+    assert!(data.is_staked);
+    assert_eq!(data.staked_in_loan, 7);
+}
+
+#[test]
+fn test_borrower_cannot_unstake() {
+    let s = setup();
+
+    mock_stake_auth(&s, &s.loan_manager, 7);
+    s.client.stake_nft(&s.token_id, &7);
+
+    mock_unstake_auth(&s, &s.borrower);
+    assert!(s.client.try_unstake_nft(&s.token_id).is_err());
+
+    let data = s.client.get_nft_data(&s.token_id);
+    assert!(data.is_staked);
+    assert_eq!(data.staked_in_loan, 7);
+}
+
+#[test]
+fn test_stake_errors() {
+    let s = setup();
+
+    mock_stake_auth(&s, &s.loan_manager, 7);
+    s.client.stake_nft(&s.token_id, &7);
+
+    mock_stake_auth(&s, &s.loan_manager, 7);
+    assert_eq!(s.client.try_stake_nft(&s.token_id, &7), Err(Ok(Error::NftAlreadyStaked)));
+
+    s.env.mock_all_auths();
+    assert_eq!(s.client.try_stake_nft(&99, &7), Err(Ok(Error::NftNotFound)));
+
+    s.client.unstake_nft(&s.token_id);
+    assert_eq!(s.client.try_unstake_nft(&s.token_id), Err(Ok(Error::NftNotStaked)));
+}
+
+#[test]
+fn test_restake_moves_nft_to_new_loan() {
+    let s = setup();
+    s.env.mock_all_auths();
+
+    assert_eq!(s.client.try_restake_nft(&s.token_id, &8), Err(Ok(Error::NftNotStaked)));
+
+    s.client.stake_nft(&s.token_id, &7);
+    s.client.restake_nft(&s.token_id, &8);
+
+    let data = s.client.get_nft_data(&s.token_id);
+    assert!(data.is_staked);
+    assert_eq!(data.staked_in_loan, 8);
+}
+
+#[test]
+fn test_loan_manager_can_seize() {
+    let s = setup();
+    let treasury = Address::generate(&s.env);
+
+    s.env.mock_all_auths();
+    assert_eq!(s.client.try_seize_nft(&s.token_id, &treasury), Err(Ok(Error::NftNotStaked)));
+    s.client.stake_nft(&s.token_id, &7);
+
+    s.env.mock_auths(&[MockAuth {
+        address: &s.borrower,
+        invoke: &MockAuthInvoke {
+            contract: &s.client.address,
+            fn_name: "seize_nft",
+            args: (s.token_id, s.borrower.clone()).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.client.try_seize_nft(&s.token_id, &s.borrower).is_err());
+
+    s.env.mock_all_auths();
+    s.client.seize_nft(&s.token_id, &treasury);
+
+    let data = s.client.get_nft_data(&s.token_id);
+    assert_eq!(data.owner, treasury);
+    assert!(!data.is_staked);
+    assert_eq!(data.staked_in_loan, 0);
+}
+
+#[test]
+fn test_transfer() {
+    let s = setup();
+    let buyer = Address::generate(&s.env);
+    s.env.mock_all_auths();
+
+    assert_eq!(s.client.try_transfer(&buyer, &buyer, &s.token_id), Err(Ok(Error::NotOwner)));
+
+    // Collateral cannot move while a loan holds it
+    s.client.stake_nft(&s.token_id, &7);
+    assert_eq!(
+        s.client.try_transfer(&s.borrower, &buyer, &s.token_id),
+        Err(Ok(Error::NftAlreadyStaked))
+    );
+    s.client.unstake_nft(&s.token_id);
+
+    s.client.transfer(&s.borrower, &buyer, &s.token_id);
+    assert_eq!(s.client.get_nft_data(&s.token_id).owner, buyer);
+}
+
+#[test]
+fn test_loan_manager_records_restructure() {
+    let s = setup();
+    s.env.mock_all_auths();
+
+    assert_eq!(s.client.try_record_restructure(&s.token_id, &7), Err(Ok(Error::NftNotStaked)));
+    s.client.stake_nft(&s.token_id, &7);
+
+    s.env.mock_auths(&[MockAuth {
+        address: &s.borrower,
+        invoke: &MockAuthInvoke {
+            contract: &s.client.address,
+            fn_name: "record_restructure",
+            args: (s.token_id, 7u64).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.client.try_record_restructure(&s.token_id, &7).is_err());
+
+    s.env.mock_all_auths();
+    s.client.record_restructure(&s.token_id, &7);
+    assert_eq!(s.client.get_nft_data(&s.token_id).restructure_count, 1);
+}
+
+#[test]
+fn test_nft_data_ttl_is_extended() {
+    let s = setup();
+    let ttl = |key: DataKey| {
+        s.env.as_contract(&s.client.address, || s.env.storage().persistent().get_ttl(&key))
+    };
+    assert_eq!(ttl(DataKey::RemittanceData(s.token_id)), common::storage::PERSISTENT_BUMP_AMOUNT);
+
+    s.env.ledger().with_mut(|l| l.sequence_number += 40 * 17_280);
+    let keys = Vec::from_array(
+        &s.env,
+        [DataKey::RemittanceData(s.token_id), DataKey::PaymentHistory(s.token_id)]
+    );
+    assert_eq!(s.client.bump(&keys), 2);
+    assert_eq!(ttl(DataKey::RemittanceData(s.token_id)), common::storage::PERSISTENT_BUMP_AMOUNT);
+    assert_eq!(ttl(DataKey::PaymentHistory(s.token_id)), common::storage::PERSISTENT_BUMP_AMOUNT);
+}
+
+#[test]
+fn test_migrate_moves_instance_storage_to_persistent() {
+    let s = setup();
+    s.env.mock_all_auths();
+    for _ in 0..54 {
+        s.client.mint(&s.borrower, &100, &90, &1, &100, &Vec::new(&s.env));
+    }
+
+    // Rewrite all 55 NFTs into the unversioned layout
+    s.env.as_contract(&s.client.address, || {
+        for token_id in 1..=55u64 {
+            for key in [DataKey::RemittanceData(token_id), DataKey::PaymentHistory(token_id)] {
+                let value: soroban_sdk::Val = s.env.storage().persistent().get(&key).unwrap();
+                s.env.storage().persistent().remove(&key);
+                s.env.storage().instance().set(&key, &value);
+            }
+        }
+        s.env.storage().instance().remove(&DataKey::SchemaVersion);
+    });
+    assert_eq!(s.client.schema_version(), 0);
+
+    assert_eq!(s.client.migrate(), 0);
+    assert_eq!(s.client.migrate(), 1);
+
+    let data = s.client.get_nft_data(&s.token_id);
+    assert_eq!(data.owner, s.borrower);
+    assert_eq!(data.reliability_score, 95);
+    assert_eq!(s.client.get_nft_data(&55).monthly_amount, 100);
+    s.env.as_contract(&s.client.address, || {
+        assert!(!s.env.storage().instance().has(&DataKey::PaymentHistory(55)));
+        assert!(s.env.storage().persistent().has(&DataKey::PaymentHistory(55)));
+    });
+}
+
+#[test]
+fn test_upgrade_requires_admin() {
+    let s = setup();
+    let hash = BytesN::from_array(&s.env, &[0; 32]);
+
+    s.env.mock_auths(&[MockAuth {
+        address: &s.borrower,
+        invoke: &MockAuthInvoke {
+            contract: &s.client.address,
+            fn_name: "upgrade",
+            args: (hash.clone(),).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.client.try_upgrade(&hash).is_err());
+}
@@ -0,0 +1,55 @@
+use soroban_sdk::{ contracttype, Address };
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuctionConfig {
+    pub duration_ledgers: u32, // ledgers for the price to fall from start to floor
+    pub reserve_bps: u32, // floor price as a share of the start price, in basis points
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Auction {
+    pub nft_id: u64,
+    pub loan_id: u64,
+    pub loan_manager: Address, // the LoanManager that seized the NFT and books the proceeds
+    pub nft_contract: Address, // the RemittanceNFT contract the NFT lives in
+    pub start_price: i128,
+    pub floor_price: i128,
+    pub start_ledger: u32,
+    pub end_ledger: u32,
+}
+
+impl Auction {
+    pub fn new(
+        nft_id: u64,
+        loan_id: u64,
+        loan_manager: Address,
+        nft_contract: Address,
+        start_price: i128,
+        config: &AuctionConfig,
+        ledger: u32
+    ) -> Self {
+        Auction {
+            nft_id,
+            loan_id,
+            loan_manager,
+            nft_contract,
+            start_price,
+            floor_price: (start_price * config.reserve_bps as i128) / 10000,
+            start_ledger: ledger,
+            end_ledger: ledger + config.duration_ledgers,
+        }
+    }
+
+    // Price falls linearly every ledger until it reaches the floor, then stays there
+    pub fn price_at(&self, ledger: u32) -> i128 {
+        if ledger >= self.end_ledger {
+            return self.floor_price;
+        }
+
+        let elapsed = (ledger.saturating_sub(self.start_ledger)) as i128;
+        let duration = (self.end_ledger - self.start_ledger) as i128;
+        self.start_price - ((self.start_price - self.floor_price) * elapsed) / duration
+    }
+}
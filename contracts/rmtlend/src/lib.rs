@@ -1,10 +1,509 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl};
+use soroban_sdk::{
+    contract,
+    contracterror,
+    contractevent,
+    contractimpl,
+    contracttype,
+    token,
+    Address,
+    BytesN,
+    Env,
+    Vec,
+};
+
+mod auction;
+mod registry;
+
+// Storage layout version written by `initialize` and brought up to date by `migrate`
+const SCHEMA_VERSION: u32 = 1;
+
+use common::access::{ self, AccessError };
+use common::storage;
+pub use auction::{ Auction, AuctionConfig };
+pub use registry::{ Component, RegistryEntry };
+
+mod nft {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/remittance_nft.wasm"
+    );
+}
+
+mod loan_manager {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/loan_manager.wasm"
+    );
+}
+
+mod pool {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/lending_pool.wasm"
+    );
+}
+
+// Duties the admin can hand out without giving away the admin key.
+// The admin itself may act in every role.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    /// Tunes auction pricing
+    RiskManager,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Component(Component), // component -> RegistryEntry
+    ComponentHistory(Component, u32), // (component, version) -> address it replaced
+    RegistryVersion,
+    USDCTokenAddress,
+    AuctionConfig,
+    Auction(u64), // nft_id -> Auction
+    SchemaVersion,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    /// The contract has already been initialized
+    AlreadyInitialized = 1,
+    /// The contract has not been initialized yet
+    NotInitialized = 2,
+    /// No auction is running for the NFT
+    AuctionNotFound = 3,
+    /// An auction is already running for the NFT
+    AuctionExists = 4,
+    /// The NFT has not been transferred to this contract
+    NftNotHeld = 5,
+    /// The current price is above the bidder's limit
+    PriceAboveLimit = 6,
+    /// The auction duration must be positive and the reserve at most 10000 basis points
+    InvalidAuctionConfig = 7,
+    /// The caller is not the admin and does not hold the required role
+    Unauthorized = 8,
+    /// No admin transfer has been proposed
+    NoPendingAdmin = 9,
+    /// The registry has no address for the component
+    ComponentNotSet = 10,
+    /// The component never had the requested version
+    VersionNotFound = 11,
+}
+
+impl From<AccessError> for Error {
+    fn from(error: AccessError) -> Self {
+        match error {
+            AccessError::Unauthorized => Error::Unauthorized,
+            AccessError::NoPendingAdmin => Error::NoPendingAdmin,
+        }
+    }
+}
+
+#[contractevent(topics = ["role_granted"], data_format = "single-value")]
+pub struct RoleGrantedEvent {
+    #[topic]
+    pub role: Role,
+    pub account: Address,
+}
+
+#[contractevent(topics = ["role_revoked"], data_format = "single-value")]
+pub struct RoleRevokedEvent {
+    #[topic]
+    pub role: Role,
+    pub account: Address,
+}
+
+#[contractevent(topics = ["auction_started"])]
+pub struct AuctionStartedEvent {
+    #[topic]
+    pub nft_id: u64,
+    #[topic]
+    pub loan_id: u64,
+    pub start_price: i128,
+    pub floor_price: i128,
+    pub end_ledger: u32,
+}
+
+#[contractevent(topics = ["auction_settled"])]
+pub struct AuctionSettledEvent {
+    #[topic]
+    pub nft_id: u64,
+    #[topic]
+    pub loan_id: u64,
+    pub buyer: Address,
+    pub price: i128,
+}
+
+#[contractevent(topics = ["registry_updated"])]
+pub struct RegistryUpdatedEvent {
+    #[topic]
+    pub component: Component,
+    pub address: Address,
+    pub version: u32,
+}
+
+#[contractevent(topics = ["upgraded"], data_format = "single-value")]
+pub struct UpgradedEvent {
+    pub wasm_hash: BytesN<32>,
+}
 
 #[contract]
 pub struct RmtLend;
 
 #[contractimpl]
 impl RmtLend {
-    // Implement contract methods here
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        loan_manager: Address,
+        nft_contract: Address,
+        pool_contract: Address,
+        oracle_contract: Address,
+        usdc_token: Address
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        admin.require_auth();
+
+        // Checked on a key that every storage layout has and `renounce_admin` leaves alone
+        if env.storage().instance().has(&DataKey::USDCTokenAddress) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        access::set_admin(&env, &admin);
+        registry::set(&env, Component::RemittanceNft, &nft_contract);
+        registry::set(&env, Component::LendingPool, &pool_contract);
+        registry::set(&env, Component::LoanManager, &loan_manager);
+        registry::set(&env, Component::OracleVerifier, &oracle_contract);
+        env.storage().instance().set(&DataKey::RegistryVersion, &1u32);
+        env.storage().instance().set(&DataKey::USDCTokenAddress, &usdc_token);
+        env.storage().instance().set(&DataKey::AuctionConfig, &AuctionConfig {
+            duration_ledgers: 17280, // about a day of 5 second ledgers
+            reserve_bps: 2000,
+        });
+        env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+
+        Ok(())
+    }
+
+    // Admin replaces the contract's code. Data is left as is; call `migrate` afterwards
+    // if the new code expects a newer storage layout.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        UpgradedEvent { wasm_hash: new_wasm_hash }.publish(&env);
+
+        Ok(())
+    }
+
+    // Storage layout version the contract's data is in; 0 for deployments that predate versioning
+    pub fn schema_version(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::SchemaVersion).unwrap_or(0)
+    }
+
+    // Admin brings stored data up to SCHEMA_VERSION and returns the version it is in afterwards.
+    // The original contract stored nothing and is set up with `initialize`, so there is no
+    // older layout to convert yet.
+    pub fn migrate(env: Env) -> Result<u32, Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        Ok(Self::schema_version(env))
+    }
+
+    pub fn get_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::admin(&env)
+    }
+
+    // Successor named by `propose_admin` who has not accepted yet
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        storage::extend_instance(&env);
+
+        access::pending_admin(&env)
+    }
+
+    // Admin nominates a successor, who takes over by calling `accept_admin`
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::propose_admin(&env, &new_admin)?)
+    }
+
+    // The nominated successor takes over as admin
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::accept_admin(&env)?)
+    }
+
+    // Admin gives up control for good; admin-only calls fail from then on
+    pub fn renounce_admin(env: Env) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        Ok(access::renounce_admin(&env)?)
+    }
+
+    // Admin lets `account` act in `role`
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::grant_role(&env, role, &account)?;
+        RoleGrantedEvent { role, account }.publish(&env);
+        Ok(())
+    }
+
+    pub fn revoke_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::revoke_role(&env, role, &account)?;
+        RoleRevokedEvent { role, account }.publish(&env);
+        Ok(())
+    }
+
+    // Whether `account` may act in `role`; the admin may act in every role
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        storage::extend_instance(&env);
+
+        access::has_role(&env, role, &account)
+    }
+
+    // Current address of a protocol contract
+    pub fn get_address(env: Env, component: Component) -> Result<Address, Error> {
+        storage::extend_instance(&env);
+
+        registry::address(&env, component)
+    }
+
+    pub fn get_component(env: Env, component: Component) -> Result<RegistryEntry, Error> {
+        storage::extend_instance(&env);
+
+        registry::entry(&env, component).ok_or(Error::ComponentNotSet)
+    }
+
+    // Every registered component, so a client can resolve the whole protocol in one call
+    pub fn get_registry(env: Env) -> Vec<RegistryEntry> {
+        storage::extend_instance(&env);
+
+        let mut entries = Vec::new(&env);
+        for component in registry::COMPONENTS {
+            if let Some(entry) = registry::entry(&env, component) {
+                entries.push_back(entry);
+            }
+        }
+        entries
+    }
+
+    // Address a component had at an earlier version
+    pub fn get_address_at(env: Env, component: Component, version: u32) -> Result<Address, Error> {
+        storage::extend_instance(&env);
+
+        registry::address_at(&env, component, version).ok_or(Error::VersionNotFound)
+    }
+
+    // Bumped on every registry update; clients caching addresses compare it to refresh
+    pub fn registry_version(env: Env) -> u32 {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::RegistryVersion).unwrap_or(0)
+    }
+
+    // Admin points a component at a new deployment and returns the component's new version.
+    // The other contracts keep their own wiring and are not updated by this.
+    pub fn set_address(env: Env, component: Component, address: Address) -> Result<u32, Error> {
+        storage::extend_instance(&env);
+
+        access::require_admin(&env)?;
+
+        let entry = registry::set(&env, component, &address);
+        let registry_version = Self::registry_version(env.clone()) + 1;
+        env.storage().instance().set(&DataKey::RegistryVersion, &registry_version);
+
+        RegistryUpdatedEvent { component, address, version: entry.version }.publish(&env);
+
+        Ok(entry.version)
+    }
+
+    // Routed to LoanManager::request_loan
+    pub fn request_loan(
+        env: Env,
+        borrower: Address,
+        nft_id: u64,
+        amount: i128,
+        duration_months: u32
+    ) -> Result<u64, Error> {
+        storage::extend_instance(&env);
+
+        let loan_manager = registry::address(&env, Component::LoanManager)?;
+        let loan_manager_client = loan_manager::Client::new(&env, &loan_manager);
+        Ok(loan_manager_client.request_loan(&borrower, &nft_id, &amount, &duration_months))
+    }
+
+    // Routed to LoanManager::make_payment
+    pub fn make_payment(env: Env, loan_id: u64, amount: i128) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let loan_manager = registry::address(&env, Component::LoanManager)?;
+        loan_manager::Client::new(&env, &loan_manager).make_payment(&loan_id, &amount);
+        Ok(())
+    }
+
+    // Routed to LendingPool::deposit
+    pub fn deposit(env: Env, lender: Address, amount: i128) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let pool_contract = registry::address(&env, Component::LendingPool)?;
+        pool::Client::new(&env, &pool_contract).deposit(&lender, &amount);
+        Ok(())
+    }
+
+    // Routed to LendingPool::withdraw
+    pub fn withdraw(env: Env, lender: Address, amount: i128) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let pool_contract = registry::address(&env, Component::LendingPool)?;
+        pool::Client::new(&env, &pool_contract).withdraw(&lender, &amount);
+        Ok(())
+    }
+
+    // List a seized NFT in a Dutch auction (called by LoanManager only)
+    pub fn start_auction(
+        env: Env,
+        loan_id: u64,
+        nft_id: u64,
+        valuation_months: u32
+    ) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        let loan_manager = registry::address(&env, Component::LoanManager)?;
+        loan_manager.require_auth();
+
+        if storage::has(&env, &DataKey::Auction(nft_id)) {
+            return Err(Error::AuctionExists);
+        }
+
+        let nft_contract = registry::address(&env, Component::RemittanceNft)?;
+        let nft_client = nft::Client::new(&env, &nft_contract);
+        if nft_client.get_nft_data(&nft_id).owner != env.current_contract_address() {
+            return Err(Error::NftNotHeld);
+        }
+
+        // Start at the value of the remittances still expected from the NFT
+        let start_price = nft_client.calculate_collateral_value(&nft_id, &valuation_months);
+        let config = Self::get_auction_config(env.clone())?;
+        let auction = Auction::new(
+            nft_id,
+            loan_id,
+            loan_manager,
+            nft_contract,
+            start_price,
+            &config,
+            env.ledger().sequence()
+        );
+
+        storage::write(&env, &DataKey::Auction(nft_id), &auction);
+
+        AuctionStartedEvent {
+            nft_id,
+            loan_id,
+            start_price: auction.start_price,
+            floor_price: auction.floor_price,
+            end_ledger: auction.end_ledger,
+        }.publish(&env);
+
+        Ok(())
+    }
+
+    // Buy the NFT at the current price; proceeds go straight to the lending pool
+    pub fn bid(env: Env, bidder: Address, nft_id: u64, max_price: i128) -> Result<i128, Error> {
+        storage::extend_instance(&env);
+
+        bidder.require_auth();
+
+        let auction = Self::get_auction(env.clone(), nft_id)?;
+        let price = auction.price_at(env.ledger().sequence());
+        if price > max_price {
+            return Err(Error::PriceAboveLimit);
+        }
+
+        storage::remove(&env, &DataKey::Auction(nft_id));
+
+        if price > 0 {
+            let usdc_token: Address = env.storage().instance().get(&DataKey::USDCTokenAddress).unwrap();
+            let pool_contract = registry::address(&env, Component::LendingPool)?;
+            let usdc_client = token::Client::new(&env, &usdc_token);
+            usdc_client.transfer(&bidder, &pool_contract, &price);
+
+            // The loan lives with the LoanManager that started the auction, even if the
+            // registry has moved on since
+            let loan_manager_client = loan_manager::Client::new(&env, &auction.loan_manager);
+            loan_manager_client.recover_defaulted(&auction.loan_id, &price);
+        }
+
+        // Likewise the NFT is held in the contract it was seized from
+        let nft_client = nft::Client::new(&env, &auction.nft_contract);
+        nft_client.transfer(&env.current_contract_address(), &bidder, &nft_id);
+
+        AuctionSettledEvent { nft_id, loan_id: auction.loan_id, buyer: bidder, price }.publish(&env);
+
+        Ok(price)
+    }
+
+    pub fn get_auction(env: Env, nft_id: u64) -> Result<Auction, Error> {
+        storage::extend_instance(&env);
+
+        storage::read(&env, &DataKey::Auction(nft_id)).ok_or(Error::AuctionNotFound)
+    }
+
+    // Price a bid would pay right now
+    pub fn current_price(env: Env, nft_id: u64) -> Result<i128, Error> {
+        storage::extend_instance(&env);
+
+        let auction = Self::get_auction(env.clone(), nft_id)?;
+        Ok(auction.price_at(env.ledger().sequence()))
+    }
+
+    // Admin sets how fast and how far auction prices fall
+    pub fn set_auction_config(env: Env, caller: Address, config: AuctionConfig) -> Result<(), Error> {
+        storage::extend_instance(&env);
+
+        access::require_role(&env, Role::RiskManager, &caller)?;
+
+        if config.duration_ledgers == 0 || config.reserve_bps > 10000 {
+            return Err(Error::InvalidAuctionConfig);
+        }
+
+        env.storage().instance().set(&DataKey::AuctionConfig, &config);
+        Ok(())
+    }
+
+    pub fn get_auction_config(env: Env) -> Result<AuctionConfig, Error> {
+        storage::extend_instance(&env);
+
+        env.storage().instance().get(&DataKey::AuctionConfig).ok_or(Error::NotInitialized)
+    }
+
+    // Anyone can extend the TTL of persistent entries, e.g. a keeper keeping live data
+    // from being archived. Returns how many of `keys` exist.
+    pub fn bump(env: Env, keys: Vec<DataKey>) -> u32 {
+        storage::extend_instance(&env);
+
+        let mut extended = 0;
+        for key in keys.iter() {
+            if storage::extend(&env, &key) {
+                extended += 1;
+            }
+        }
+        extended
+    }
 }
+
+#[cfg(test)]
+mod test;
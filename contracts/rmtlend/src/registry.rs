@@ -0,0 +1,73 @@
+use soroban_sdk::{ contracttype, Address, Env };
+
+use common::storage;
+use crate::{ DataKey, Error };
+
+// Protocol contracts whose current address the registry keeps
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Component {
+    RemittanceNft,
+    LendingPool,
+    LoanManager,
+    OracleVerifier,
+}
+
+// Every component, in the order `get_registry` lists them
+pub const COMPONENTS: [Component; 4] = [
+    Component::RemittanceNft,
+    Component::LendingPool,
+    Component::LoanManager,
+    Component::OracleVerifier,
+];
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegistryEntry {
+    pub component: Component,
+    pub address: Address,
+    pub version: u32, // 1 for the first address, one more on each update
+    pub updated_ledger: u32,
+}
+
+pub fn entry(env: &Env, component: Component) -> Option<RegistryEntry> {
+    env.storage().instance().get(&DataKey::Component(component))
+}
+
+pub fn address(env: &Env, component: Component) -> Result<Address, Error> {
+    entry(env, component)
+        .map(|entry| entry.address)
+        .ok_or(Error::ComponentNotSet)
+}
+
+// Address `component` had at `version`, whether current or replaced since
+pub fn address_at(env: &Env, component: Component, version: u32) -> Option<Address> {
+    match entry(env, component) {
+        Some(current) if current.version == version => Some(current.address),
+        _ => storage::read(env, &DataKey::ComponentHistory(component, version)),
+    }
+}
+
+// Point `component` at `address`; the address it replaces stays readable under its old version
+pub fn set(env: &Env, component: Component, address: &Address) -> RegistryEntry {
+    let version = match entry(env, component) {
+        Some(previous) => {
+            storage::write(
+                env,
+                &DataKey::ComponentHistory(component, previous.version),
+                &previous.address
+            );
+            previous.version + 1
+        }
+        None => 1,
+    };
+
+    let entry = RegistryEntry {
+        component,
+        address: address.clone(),
+        version,
+        updated_ledger: env.ledger().sequence(),
+    };
+    env.storage().instance().set(&DataKey::Component(component), &entry);
+    entry
+}
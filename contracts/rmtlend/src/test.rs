@@ -0,0 +1,279 @@
+use super::*;
+use soroban_sdk::{
+    contract,
+    contractimpl,
+    testutils::{ Address as _, Ledger, MockAuth, MockAuthInvoke },
+    token::{ StellarAssetClient, TokenClient },
+    IntoVal,
+    Vec,
+};
+
+// Stand-in for OracleVerifier, which LoanManager notifies on approval
+#[contract]
+struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    pub fn start_monitoring_loan(_env: Env, _loan_id: u64) {}
+}
+
+struct Setup<'a> {
+    env: Env,
+    rmtlend: RmtLendClient<'a>,
+    loan_manager: loan_manager::Client<'a>,
+    pool: pool::Client<'a>,
+    nft: nft::Client<'a>,
+    token: TokenClient<'a>,
+    admin: Address,
+    buyer: Address,
+    nft_id: u64,
+    loan_id: u64,
+}
+
+// A 10_000 loan at 21% APR that defaults after its first installment
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let admin = Address::generate(&env);
+    let lender = Address::generate(&env);
+    let borrower = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let token = TokenClient::new(&env, &usdc.address());
+    let token_admin = StellarAssetClient::new(&env, &usdc.address());
+    token_admin.mint(&lender, &50_000);
+    token_admin.mint(&buyer, &100_000);
+
+    let nft_id = env.register(nft::WASM, ());
+    let pool_id = env.register(pool::WASM, ());
+    let loan_manager_id = env.register(loan_manager::WASM, ());
+    let oracle_id = env.register(MockOracle, ());
+    let rmtlend_id = env.register(RmtLend, ());
+
+    let nft = nft::Client::new(&env, &nft_id);
+    let pool = pool::Client::new(&env, &pool_id);
+    let loan_manager = loan_manager::Client::new(&env, &loan_manager_id);
+    let rmtlend = RmtLendClient::new(&env, &rmtlend_id);
+
+    nft.initialize(&admin, &oracle_id, &loan_manager_id);
+    pool.initialize(&admin, &loan_manager_id, &usdc.address(), &500);
+    loan_manager.initialize(&admin, &nft_id, &pool_id, &oracle_id, &usdc.address());
+    rmtlend.initialize(&admin, &loan_manager_id, &nft_id, &pool_id, &oracle_id, &usdc.address());
+    loan_manager.set_auction_house(&rmtlend_id);
+    pool.deposit(&lender, &50_000);
+
+    let token_id = nft.mint(&borrower, &10_000, &75, &12, &120_000, &Vec::new(&env));
+    let loan_id = loan_manager.request_loan(&borrower, &token_id, &10_000, &12);
+    loan_manager.approve_loan(&loan_id);
+    loan_manager.make_payment(&loan_id, &932);
+
+    loan_manager.mark_payment_missed(&loan_id);
+    loan_manager.mark_payment_missed(&loan_id);
+
+    Setup { env, rmtlend, loan_manager, pool, nft, token, admin, buyer, nft_id: token_id, loan_id }
+}
+
+#[test]
+fn test_default_lists_nft_at_collateral_value() {
+    let s = setup();
+
+    assert_eq!(s.nft.get_nft_data(&s.nft_id).owner, s.rmtlend.address);
+
+    // 11 unpaid months of 10_000 at a score of 75, less the 30% haircut
+    let auction = s.rmtlend.get_auction(&s.nft_id);
+    assert_eq!(auction.loan_id, s.loan_id);
+    assert_eq!(auction.loan_manager, s.loan_manager.address);
+    assert_eq!(auction.nft_contract, s.nft.address);
+    assert_eq!(auction.start_price, 57_750);
+    assert_eq!(auction.floor_price, 11_550);
+    assert_eq!(auction.end_ledger, auction.start_ledger + 17_280);
+    assert_eq!(s.rmtlend.current_price(&s.nft_id), 57_750);
+}
+
+#[test]
+fn test_price_decays_per_ledger() {
+    let s = setup();
+
+    s.env.ledger().with_mut(|l| l.sequence_number += 1);
+    assert_eq!(s.rmtlend.current_price(&s.nft_id), 57_748);
+
+    s.env.ledger().with_mut(|l| l.sequence_number += 8_639);
+    assert_eq!(s.rmtlend.current_price(&s.nft_id), 34_650);
+
+    // The price rests at the floor once the auction runs out
+    s.env.ledger().with_mut(|l| l.sequence_number += 20_000);
+    assert_eq!(s.rmtlend.current_price(&s.nft_id), 11_550);
+}
+
+#[test]
+fn test_bid_pays_pool_and_transfers_nft() {
+    let s = setup();
+    let assets_after_default = s.pool.total_assets();
+    assert_eq!(assets_after_default, 50_000 - 9_243);
+
+    s.env.ledger().with_mut(|l| l.sequence_number += 8_640);
+    assert_eq!(
+        s.rmtlend.try_bid(&s.buyer, &s.nft_id, &34_649),
+        Err(Ok(Error::PriceAboveLimit))
+    );
+
+    assert_eq!(s.rmtlend.bid(&s.buyer, &s.nft_id, &34_650), 34_650);
+
+    assert_eq!(s.nft.get_nft_data(&s.nft_id).owner, s.buyer);
+    assert_eq!(s.token.balance(&s.buyer), 100_000 - 34_650);
+    assert_eq!(s.pool.total_recovered(), 34_650);
+    assert_eq!(s.pool.total_assets(), assets_after_default + 34_650);
+
+    assert_eq!(s.rmtlend.try_get_auction(&s.nft_id), Err(Ok(Error::AuctionNotFound)));
+    assert_eq!(s.rmtlend.try_bid(&s.buyer, &s.nft_id, &100_000), Err(Ok(Error::AuctionNotFound)));
+}
+
+#[test]
+fn test_bid_settles_with_the_contracts_that_started_the_auction() {
+    let s = setup();
+
+    // A new LoanManager and NFT contract are registered while the auction runs
+    let new_loan_manager = s.env.register(loan_manager::WASM, ());
+    s.rmtlend.set_address(&Component::LoanManager, &new_loan_manager);
+    let new_nft = s.env.register(nft::WASM, ());
+    s.rmtlend.set_address(&Component::RemittanceNft, &new_nft);
+
+    assert_eq!(s.rmtlend.bid(&s.buyer, &s.nft_id, &57_750), 57_750);
+    assert_eq!(s.pool.total_recovered(), 57_750);
+    assert_eq!(s.nft.get_nft_data(&s.nft_id).owner, s.buyer);
+}
+
+#[test]
+fn test_auction_admin() {
+    let s = setup();
+
+    assert_eq!(
+        s.rmtlend.try_start_auction(&s.loan_id, &s.nft_id, &12),
+        Err(Ok(Error::AuctionExists))
+    );
+    assert!(s.rmtlend.try_start_auction(&s.loan_id, &99, &12).is_err());
+
+    let config = AuctionConfig { duration_ledgers: 100, reserve_bps: 5_000 };
+    s.rmtlend.set_auction_config(&s.admin, &config);
+    assert_eq!(s.rmtlend.get_auction_config(), config);
+
+    assert_eq!(
+        s.rmtlend.try_set_auction_config(&s.admin, &AuctionConfig { duration_ledgers: 0, reserve_bps: 5_000 }),
+        Err(Ok(Error::InvalidAuctionConfig))
+    );
+    assert_eq!(
+        s.rmtlend.try_set_auction_config(&s.admin, &AuctionConfig { duration_ledgers: 100, reserve_bps: 10_001 }),
+        Err(Ok(Error::InvalidAuctionConfig))
+    );
+}
+
+#[test]
+fn test_only_auction_house_books_recoveries() {
+    let s = setup();
+
+    s.env.mock_auths(&[MockAuth {
+        address: &s.buyer,
+        invoke: &MockAuthInvoke {
+            contract: &s.loan_manager.address,
+            fn_name: "recover_defaulted",
+            args: (s.loan_id, 1_000i128).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.loan_manager.try_recover_defaulted(&s.loan_id, &1_000).is_err());
+    assert_eq!(s.pool.total_recovered(), 0);
+}
+
+#[test]
+fn test_renounced_admin_cannot_reinitialize() {
+    let s = setup();
+
+    s.rmtlend.renounce_admin();
+    assert_eq!(s.rmtlend.get_admin(), None);
+    assert_eq!(
+        s.rmtlend.try_set_auction_config(&s.admin, &AuctionConfig { duration_ledgers: 100, reserve_bps: 5_000 }),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        s.rmtlend.try_initialize(
+            &s.admin,
+            &s.loan_manager.address,
+            &s.nft.address,
+            &s.pool.address,
+            &s.buyer,
+            &s.token.address
+        ),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+}
+
+#[test]
+fn test_registry_versions_addresses() {
+    let s = setup();
+
+    let registry = s.rmtlend.get_registry();
+    assert_eq!(registry.len(), 4);
+    assert_eq!(registry.get(0).unwrap().address, s.nft.address);
+    assert_eq!(s.rmtlend.get_address(&Component::LoanManager), s.loan_manager.address);
+    assert_eq!(s.rmtlend.get_component(&Component::LendingPool).version, 1);
+    assert_eq!(s.rmtlend.registry_version(), 1);
+
+    let new_oracle = Address::generate(&s.env);
+    let old_oracle = s.rmtlend.get_address(&Component::OracleVerifier);
+    assert_eq!(s.rmtlend.set_address(&Component::OracleVerifier, &new_oracle), 2);
+    assert_eq!(s.rmtlend.get_address(&Component::OracleVerifier), new_oracle);
+    assert_eq!(s.rmtlend.get_address_at(&Component::OracleVerifier, &1), old_oracle);
+    assert_eq!(s.rmtlend.get_address_at(&Component::OracleVerifier, &2), new_oracle);
+    assert_eq!(
+        s.rmtlend.try_get_address_at(&Component::OracleVerifier, &3),
+        Err(Ok(Error::VersionNotFound))
+    );
+    assert_eq!(s.rmtlend.registry_version(), 2);
+
+    s.env.mock_auths(&[MockAuth {
+        address: &s.buyer,
+        invoke: &MockAuthInvoke {
+            contract: &s.rmtlend.address,
+            fn_name: "set_address",
+            args: (Component::LendingPool, s.buyer.clone()).into_val(&s.env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(s.rmtlend.try_set_address(&Component::LendingPool, &s.buyer).is_err());
+}
+
+#[test]
+fn test_routed_calls() {
+    let s = setup();
+    let borrower = Address::generate(&s.env);
+    let lender = Address::generate(&s.env);
+    StellarAssetClient::new(&s.env, &s.token.address).mint(&lender, &20_000);
+
+    s.rmtlend.deposit(&lender, &20_000);
+    assert_eq!(s.pool.get_lender_info(&lender).deposit_amount, 20_000);
+
+    let nft_id = s.nft.mint(&borrower, &10_000, &95, &12, &120_000, &Vec::new(&s.env));
+    let loan_id = s.rmtlend.request_loan(&borrower, &nft_id, &5_000, &12);
+
+    let loan = s.loan_manager.get_loan(&loan_id);
+    assert_eq!(loan.borrower, borrower);
+    assert_eq!(loan.nft_collateral_id, nft_id);
+
+    s.loan_manager.approve_loan(&loan_id);
+    s.rmtlend.make_payment(&loan_id, &loan.monthly_payment);
+    assert_eq!(s.loan_manager.get_loan(&loan_id).payments_made, 1);
+
+    s.rmtlend.withdraw(&lender, &1_000);
+    assert_eq!(s.token.balance(&lender), 1_000);
+}
+
+#[test]
+fn test_migrate_leaves_current_layout_alone() {
+    let s = setup();
+
+    assert_eq!(s.rmtlend.schema_version(), 1);
+    assert_eq!(s.rmtlend.migrate(), 1);
+    assert_eq!(s.rmtlend.get_address(&Component::LoanManager), s.loan_manager.address);
+}
@@ -115,6 +115,20 @@ stellar contract invoke \
   --oracle_contract $REMITTANCE_NFT_ID \
   --usdc_token $USDC_TOKEN || echo "⚠️  LoanManager initialization skipped (might use __initialize)"
 
+# Initialize RmtLend, which registers the other contracts' addresses
+echo "Initializing RmtLend..."
+stellar contract invoke \
+  --id $RMTLEND_ID \
+  --source default \
+  --network testnet \
+  -- initialize \
+  --admin $(stellar keys address default) \
+  --loan_manager $LOAN_MANAGER_ID \
+  --nft_contract $REMITTANCE_NFT_ID \
+  --pool_contract $LENDING_POOL_ID \
+  --oracle_contract $REMITTANCE_NFT_ID \
+  --usdc_token $USDC_TOKEN || echo "⚠️  RmtLend initialization skipped"
+
 echo ""
 echo "✅ All contracts deployed and initialized!"
 echo ""
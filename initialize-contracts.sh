@@ -11,7 +11,8 @@ stellar contract invoke \
   --id $PUBLIC_REMITTANCE_NFT_CONTRACT_ID \
   --source alice \
   --network testnet \
-  -- __initialize \
+  -- initialize \
+  --admin "$(stellar keys address alice)" \
   --oracle "$PUBLIC_REMITTANCE_NFT_CONTRACT_ID" \
   --loan_manager "$PUBLIC_LOAN_MANAGER_CONTRACT_ID"
 
@@ -29,7 +30,8 @@ stellar contract invoke \
   --id $PUBLIC_LOAN_MANAGER_CONTRACT_ID \
   --source alice \
   --network testnet \
-  -- __initialize \
+  -- initialize \
+  --admin "$(stellar keys address alice)" \
   --nft_contract "$PUBLIC_REMITTANCE_NFT_CONTRACT_ID" \
   --pool_contract "$PUBLIC_LENDING_POOL_CONTRACT_ID" \
   --oracle_contract "$PUBLIC_REMITTANCE_NFT_CONTRACT_ID" \